@@ -0,0 +1,13 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate capnp;
+
+use capnp::{any_pointer, message, serialize_packed};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(message) = serialize_packed::read_message(&mut &data[..], message::ReaderOptions::new()) {
+        if let Ok(root) = message.get_root::<any_pointer::Reader>() {
+            let _ = root.total_size();
+        }
+    }
+});
@@ -0,0 +1,34 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate capnp;
+
+use capnp::{any_pointer, data, data_list, list_list, message, primitive_list, serialize, text,
+            text_list};
+
+fn try_read(message: &message::Reader<serialize::OwnedSegments>) -> capnp::Result<()> {
+    let root: any_pointer::Reader = try!(message.get_root());
+    let _ = root.get_as::<text::Reader>();
+    let _ = root.get_as::<data::Reader>();
+    if let Ok(list) = root.get_as::<primitive_list::Reader<u64>>() {
+        for idx in 0..list.len() { list.get(idx); }
+    }
+    if let Ok(list) = root.get_as::<text_list::Reader>() {
+        for idx in 0..list.len() { let _ = list.get(idx); }
+    }
+    if let Ok(list) = root.get_as::<data_list::Reader>() {
+        for idx in 0..list.len() { let _ = list.get(idx); }
+    }
+    if let Ok(list) = root.get_as::<list_list::Reader<primitive_list::Owned<u8>>>() {
+        for idx in 0..list.len() { let _ = list.get(idx); }
+    }
+    Ok(())
+}
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(message) = serialize::read_message(&mut &data[..], message::ReaderOptions::new()) {
+        let _ = try_read(&message);
+        if message.validate().is_ok() {
+            try_read(&message).expect("read failed after successful validation");
+        }
+    }
+});
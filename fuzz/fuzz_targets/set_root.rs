@@ -0,0 +1,18 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate capnp;
+
+use capnp::{any_pointer, message, serialize};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(reader) = serialize::read_message(&mut &data[..], message::ReaderOptions::new()) {
+        if let Ok(root) = reader.get_root::<any_pointer::Reader>() {
+            let mut builder = message::Builder::new_default();
+            if builder.set_root(root).is_ok() {
+                // Copying again overwrites, and therefore zeroes, the first copy.
+                let _ = builder.set_root(root);
+                let _ = builder.get_root_as_reader::<any_pointer::Reader>().unwrap().total_size();
+            }
+        }
+    }
+});
@@ -0,0 +1,143 @@
+// Copyright (c) 2013-2015 Sandstorm Development Group, Inc. and contributors
+// Licensed under the MIT License:
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! A list of unknown type.
+//!
+//! `initn_as()` has no way to know an element size, so it initializes a list of voids; to pick
+//! the element size, use `any_pointer::Builder::init_any_list()` or `init_any_struct_list()`.
+
+use private::layout::{ListBuilder, ListReader, PointerBuilder, PointerReader};
+use traits::{FromPointerReader, FromPointerBuilder};
+use Result;
+
+pub use private::layout::ElementSize;
+
+#[derive(Copy, Clone)]
+pub struct Owned;
+
+impl <'a> ::traits::Owned<'a> for Owned {
+    type Reader = Reader<'a>;
+    type Builder = Builder<'a>;
+}
+
+#[derive(Clone, Copy)]
+pub struct Reader<'a> {
+    reader: ListReader<'a>
+}
+
+impl <'a> Reader<'a> {
+    pub fn new(reader: ListReader<'a>) -> Reader<'a> {
+        Reader { reader: reader }
+    }
+
+    pub fn len(&self) -> u32 { self.reader.len() }
+
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    pub fn get_element_size(&self) -> ElementSize { self.reader.get_element_size() }
+
+    /// Reads element `index` as a struct. Works for any list that could be read as a list of
+    /// structs, with the same upgrade rules as `struct_list`.
+    pub fn get_struct_element(&self, index: u32) -> ::any_struct::Reader<'a> {
+        assert!(index < self.len());
+        ::any_struct::Reader::new(self.reader.get_struct_element(index))
+    }
+
+    /// Reads element `index` of a list of pointers.
+    pub fn get_pointer_element(&self, index: u32) -> ::any_pointer::Reader<'a> {
+        assert!(index < self.len());
+        assert_eq!(self.get_element_size(), ElementSize::Pointer);
+        ::any_pointer::Reader::new(self.reader.get_pointer_element(index))
+    }
+}
+
+impl <'a> FromPointerReader<'a> for Reader<'a> {
+    fn get_from_pointer(reader: &PointerReader<'a>) -> Result<Reader<'a>> {
+        Ok(Reader { reader: try!(reader.get_list(ElementSize::Void, ::std::ptr::null())) })
+    }
+}
+
+impl <'a> ::traits::SetPointerBuilder<Builder<'a>> for Reader<'a> {
+    fn set_pointer_builder<'b>(pointer: PointerBuilder<'b>, value: Reader<'a>) -> Result<()> {
+        pointer.set_list(&value.reader)
+    }
+}
+
+pub struct Builder<'a> {
+    builder: ListBuilder<'a>
+}
+
+impl <'a> Builder<'a> {
+    pub fn new(builder: ListBuilder<'a>) -> Builder<'a> {
+        Builder { builder: builder }
+    }
+
+    pub fn len(&self) -> u32 { self.builder.len() }
+
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Drops every element from index `len` on, along with anything the elements point to.
+    /// Errors if `len` exceeds the length.
+    pub fn truncate(&mut self, len: u32) -> Result<()> {
+        self.builder.truncate(len)
+    }
+
+    /// Sets the length of the list to `len`. New elements are zero or null. Growing the list
+    /// moves it to a new location in the message, so builders previously obtained for
+    /// the list or its elements must not be used afterwards.
+    pub fn resize(&mut self, len: u32) -> Result<()> {
+        self.builder.resize(len)
+    }
+
+    pub fn get_element_size(&self) -> ElementSize { self.builder.get_element_size() }
+
+    pub fn as_reader(self) -> Reader<'a> {
+        Reader { reader: self.builder.as_reader() }
+    }
+
+    pub fn borrow<'b>(&'b mut self) -> Builder<'b> {
+        Builder { builder: self.builder.borrow() }
+    }
+
+    /// Gets element `index` of a list of structs.
+    pub fn get_struct_element(self, index: u32) -> ::any_struct::Builder<'a> {
+        assert!(index < self.len());
+        assert_eq!(self.get_element_size(), ElementSize::InlineComposite);
+        ::any_struct::Builder::new(self.builder.get_struct_element(index))
+    }
+
+    /// Gets element `index` of a list of pointers.
+    pub fn get_pointer_element(self, index: u32) -> ::any_pointer::Builder<'a> {
+        assert!(index < self.len());
+        assert_eq!(self.get_element_size(), ElementSize::Pointer);
+        ::any_pointer::Builder::new(self.builder.get_pointer_element(index))
+    }
+}
+
+impl <'a> FromPointerBuilder<'a> for Builder<'a> {
+    fn init_pointer(builder: PointerBuilder<'a>, size: u32) -> Builder<'a> {
+        Builder { builder: builder.init_list(ElementSize::Void, size) }
+    }
+
+    fn get_from_pointer(builder: PointerBuilder<'a>) -> Result<Builder<'a>> {
+        Ok(Builder { builder: try!(builder.get_list(ElementSize::Void, ::std::ptr::null())) })
+    }
+}
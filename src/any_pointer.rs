@@ -23,8 +23,8 @@
 
 use capability::FromClientHook;
 use private::capability::{ClientHook, PipelineHook, PipelineOp};
-use private::layout::{PointerReader, PointerBuilder};
-use traits::{FromPointerReader, FromPointerBuilder, SetPointerBuilder};
+use private::layout::{ElementSize, PointerReader, PointerBuilder, StructSize};
+use traits::{FromPointerReader, FromPointerBuilder, SetListElement, SetPointerBuilder};
 use Result;
 
 #[derive(Copy, Clone)]
@@ -39,6 +39,18 @@ impl ::traits::Pipelined for Owned {
     type Pipeline = Pipeline;
 }
 
+/// The kind of object a pointer points to, as reported by `Reader::which()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Which {
+    Null,
+    Struct { data_words: u16, pointer_count: u16 },
+    List { element_size: ElementSize, element_count: u32 },
+    Capability { index: u32 },
+
+    /// A pointer of a kind this implementation does not understand.
+    Other,
+}
+
 #[derive(Copy, Clone)]
 pub struct Reader<'a> {
     reader: PointerReader<'a>
@@ -60,6 +72,18 @@ impl <'a> Reader<'a> {
         self.reader.total_size()
     }
 
+    /// Reports what kind of object the pointer points to, following far pointers, so that
+    /// callers can decide how to interpret it before calling `get_as()`.
+    pub fn which(&self) -> Result<Which> {
+        self.reader.get_pointer_type()
+    }
+
+    /// Checks that the target and all of its children are well-formed and within the nesting
+    /// and traversal limits. See `message::Reader::validate()`.
+    pub fn validate(&self) -> Result<()> {
+        self.reader.validate()
+    }
+
     #[inline]
     pub fn get_as<T: FromPointerReader<'a>>(&self) -> Result<T> {
         FromPointerReader::get_from_pointer(&self.reader)
@@ -141,17 +165,68 @@ impl <'a> Builder<'a> {
         FromPointerBuilder::init_pointer(self.builder, size)
     }
 
+    /// Initializes the pointer as a list with one element per item of `values`. The list's
+    /// length is taken from `len()`; should the iterator yield more items than that, the extra
+    /// ones are ignored, and should it yield fewer, the remaining elements are left zero or null.
+    pub fn init_from_iter<T, I>(self, values: I) -> T
+        where T: FromPointerBuilder<'a> + SetListElement<I::Item>,
+              I: IntoIterator, I::IntoIter: ExactSizeIterator
+    {
+        let iter = values.into_iter();
+        assert!(iter.len() <= u32::MAX as usize, "too many list elements");
+        let len = iter.len();
+        let mut list: T = self.initn_as(len as u32);
+        for (idx, value) in iter.take(len).enumerate() {
+            list.set_element(idx as u32, value);
+        }
+        list
+    }
+
+    /// Initializes the pointer as a struct with the given section sizes.
+    pub fn init_any_struct(self, data_words: u16, pointer_count: u16) -> ::any_struct::Builder<'a> {
+        let size = StructSize { data: data_words, pointers: pointer_count };
+        ::any_struct::Builder::new(self.builder.init_struct(size))
+    }
+
+    /// Initializes the pointer as a list of non-struct elements. For a list of structs, use
+    /// `init_any_struct_list()`.
+    pub fn init_any_list(self, element_size: ElementSize, element_count: u32) -> ::any_list::Builder<'a> {
+        assert!(element_size != ElementSize::InlineComposite,
+                "Use init_any_struct_list() to initialize a list of structs.");
+        ::any_list::Builder::new(self.builder.init_list(element_size, element_count))
+    }
+
+    /// Initializes the pointer as a list of structs with the given section sizes.
+    pub fn init_any_struct_list(self, element_count: u32, data_words: u16, pointer_count: u16)
+                                -> ::any_list::Builder<'a>
+    {
+        let size = StructSize { data: data_words, pointers: pointer_count };
+        ::any_list::Builder::new(self.builder.init_struct_list(element_count, size))
+    }
+
     pub fn set_as<To, From : SetPointerBuilder<To>>(self, value: From) -> Result<()> {
         SetPointerBuilder::<To>::set_pointer_builder(self.builder, value)
     }
 
+    /// Deep-copies `value` into this pointer, handing each capability it contains to `translate`
+    /// first. The translator can pass the capability through, replace it (e.g. with a proxy),
+    /// drop it by returning `Ok(None)`, or fail the whole copy, which leaves this pointer null.
+    /// Useful when forwarding a message into another trust domain, where capabilities must not
+    /// leak through unchanged.
+    pub fn set_with_cap_translator<F>(self, value: Reader, mut translate: F) -> Result<()>
+        where F: FnMut(Box<ClientHook>) -> Result<Option<Box<ClientHook>>>
+    {
+        let mut builder = self.builder;
+        builder.copy_from_with_caps(value.reader, &mut translate)
+    }
+
     // XXX value should be a user client.
     pub fn set_as_capability(&mut self, value: Box<ClientHook>) {
         self.builder.set_capability(value);
     }
 
     #[inline]
-    pub fn clear(&mut self) {
+    pub fn clear(&mut self) -> Result<()> {
         self.builder.clear()
     }
 
@@ -164,7 +239,7 @@ impl <'a> Builder<'a> {
 impl <'a> FromPointerBuilder<'a> for Builder<'a> {
     fn init_pointer(mut builder: PointerBuilder<'a>, _len: u32) -> Builder<'a> {
         if !builder.is_null() {
-            builder.clear();
+            ::private::layout::expect_well_formed(builder.clear());
         }
         Builder { builder: builder }
     }
@@ -239,3 +314,296 @@ fn init_clears_value() {
         assert_eq!(*byte, 0u8);
     }
 }
+
+#[test]
+fn clear_reports_malformed_value() {
+    // Hands out a first segment whose root is a double-far pointer with a malformed landing pad.
+    struct Garbage(Vec<::Word>);
+    unsafe impl ::message::Allocator for Garbage {
+        fn allocate_segment(&mut self, _minimum_size: u32) -> (*mut ::Word, u32) {
+            (self.0.as_mut_ptr(), self.0.len() as u32)
+        }
+    }
+
+    let mut words = ::Word::allocate_zeroed_vec(4);
+    words[0] = capnp_word!(0x0e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00);
+    let mut message = ::message::Builder::new(Garbage(words));
+    let mut root: ::any_pointer::Builder = message.get_root().unwrap();
+    assert!(root.clear().is_err());
+}
+
+#[test]
+fn scratch_space_starts_out_zeroed() {
+    let mut words = ::Word::allocate_zeroed_vec(4);
+    words[0] = capnp_word!(0x0e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00);
+    let mut scratch_space = ::message::ScratchSpace::new(&mut words);
+    let mut message =
+        ::message::Builder::new(::message::ScratchSpaceHeapAllocator::new(&mut scratch_space));
+    let root: ::any_pointer::Builder = message.init_root();
+    assert!(root.is_null());
+}
+
+#[test]
+fn any_struct_and_any_list() {
+    let mut message = ::message::Builder::new_default();
+    {
+        let root: ::any_pointer::Builder = message.init_root();
+        let mut st = root.init_any_struct(1, 2);
+        st.borrow().get_data_section()[0] = 7;
+        {
+            let mut list = st.borrow().get_pointer_field(1).init_any_list(ElementSize::Pointer, 3);
+            list.borrow().get_pointer_element(2).set_as::<::text::Builder, _>("abc").unwrap();
+        }
+        st.get_pointer_field(0).init_any_struct_list(2, 1, 0);
+    }
+
+    let root: ::any_struct::Reader = message.get_root_as_reader().unwrap();
+    assert_eq!(root.get_data_section(), &[7, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(root.get_pointer_section_size(), 2);
+    assert!(root.get_pointer_field(2).is_null());
+
+    let structs: ::any_list::Reader = root.get_pointer_field(0).get_as().unwrap();
+    assert_eq!(structs.get_element_size(), ElementSize::InlineComposite);
+    assert_eq!(structs.len(), 2);
+    assert_eq!(structs.get_struct_element(1).get_data_section().len(), 8);
+
+    let pointers: ::any_list::Reader = root.get_pointer_field(1).get_as().unwrap();
+    assert_eq!(pointers.get_element_size(), ElementSize::Pointer);
+    assert_eq!(pointers.len(), 3);
+    assert!(pointers.get_pointer_element(0).is_null());
+    assert_eq!(pointers.get_pointer_element(2).get_as::<::text::Reader>().unwrap(), "abc");
+}
+
+#[test]
+fn init_as_any_struct_and_any_list() {
+    let mut message = ::message::Builder::new_default();
+    {
+        let mut st = message.init_root::<::any_pointer::Builder>().init_any_struct(0, 2);
+        let empty: ::any_struct::Builder = st.borrow().get_pointer_field(0).init_as();
+        assert_eq!(empty.get_pointer_section_size(), 0);
+        let voids: ::any_list::Builder = st.get_pointer_field(1).initn_as(4);
+        assert_eq!(voids.get_element_size(), ElementSize::Void);
+        assert_eq!(voids.len(), 4);
+    }
+
+    let root: ::any_struct::Reader = message.get_root_as_reader().unwrap();
+    let empty: ::any_struct::Reader = root.get_pointer_field(0).get_as().unwrap();
+    assert_eq!(empty.get_data_section().len(), 0);
+    let voids: ::any_list::Reader = root.get_pointer_field(1).get_as().unwrap();
+    assert_eq!(voids.len(), 4);
+}
+
+#[test]
+fn init_from_iter_with_wrong_len() {
+    // An iterator whose `len()` disagrees with the number of items it yields.
+    struct Lying { len: usize, items: ::std::ops::Range<u16> }
+    impl Iterator for Lying {
+        type Item = u16;
+        fn next(&mut self) -> Option<u16> { self.items.next() }
+    }
+    impl ExactSizeIterator for Lying {
+        fn len(&self) -> usize { self.len }
+    }
+
+    let mut message = ::message::Builder::new_default();
+    {
+        let mut st = message.init_root::<Builder>().init_any_struct(0, 2);
+        st.borrow().get_pointer_field(0).init_from_iter::<::primitive_list::Builder<u16>, _>(
+            Lying { len: 2, items: 1..100 });
+        st.borrow().get_pointer_field(1).init_from_iter::<::primitive_list::Builder<u16>, _>(
+            Lying { len: 4, items: 1..3 });
+    }
+
+    let st: ::any_struct::Reader = message.get_root_as_reader().unwrap();
+    let short: ::primitive_list::Reader<u16> = st.get_pointer_field(0).get_as().unwrap();
+    assert_eq!(short.iter().collect::<Vec<_>>(), vec![1, 2]);
+    let long: ::primitive_list::Reader<u16> = st.get_pointer_field(1).get_as().unwrap();
+    assert_eq!(long.iter().collect::<Vec<_>>(), vec![1, 2, 0, 0]);
+}
+
+#[test]
+fn which() {
+    let mut message = ::message::Builder::new_default();
+    {
+        let mut st = message.init_root::<::any_pointer::Builder>().init_any_struct(1, 3);
+        st.borrow().get_pointer_field(0).init_any_list(ElementSize::TwoBytes, 5);
+        st.borrow().get_pointer_field(1).init_any_struct_list(4, 2, 1);
+    }
+
+    let root: Reader = message.get_root_as_reader().unwrap();
+    assert_eq!(root.which().unwrap(), Which::Struct { data_words: 1, pointer_count: 3 });
+
+    let st: ::any_struct::Reader = root.get_as().unwrap();
+    assert_eq!(st.get_pointer_field(0).which().unwrap(),
+               Which::List { element_size: ElementSize::TwoBytes, element_count: 5 });
+    assert_eq!(st.get_pointer_field(1).which().unwrap(),
+               Which::List { element_size: ElementSize::InlineComposite, element_count: 4 });
+    assert_eq!(st.get_pointer_field(2).which().unwrap(), Which::Null);
+    assert_eq!(st.get_pointer_field(3).which().unwrap(), Which::Null);
+}
+
+#[test]
+fn which_follows_fars_and_reports_caps() {
+    let segment0: &[::Word] = &[
+        capnp_word!(0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00),  // root: 4 pointers
+        capnp_word!(0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00),  // far to segment 1, word 0
+        capnp_word!(0x16, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00),  // double-far to segment 1, word 2
+        capnp_word!(0x03, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00),  // capability 2
+        capnp_word!(0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00)]; // reserved "other" pointer
+    let segment1: &[::Word] = &[
+        capnp_word!(0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00),  // landing pad: struct, 1 data word
+        capnp_word!(0x2a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00),
+        capnp_word!(0x02, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00),  // far to segment 2, word 0
+        capnp_word!(0x01, 0x00, 0x00, 0x00, 0x1a, 0x00, 0x00, 0x00)]; // tag: 3 bytes
+    let segment2: &[::Word] = &[
+        capnp_word!(0x01, 0x02, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00)];
+    let segments: &[&[::Word]] = &[segment0, segment1, segment2];
+    let message = ::message::Reader::new(::message::SegmentArray::new(segments),
+                                         ::message::ReaderOptions::new());
+
+    let root: Reader = message.get_root().unwrap();
+    assert_eq!(root.which().unwrap(), Which::Struct { data_words: 0, pointer_count: 4 });
+    let st: ::any_struct::Reader = root.get_as().unwrap();
+    assert_eq!(st.get_pointer_field(0).which().unwrap(),
+               Which::Struct { data_words: 1, pointer_count: 0 });
+    assert_eq!(st.get_pointer_field(1).which().unwrap(),
+               Which::List { element_size: ElementSize::Byte, element_count: 3 });
+    assert_eq!(st.get_pointer_field(2).which().unwrap(), Which::Capability { index: 2 });
+    assert_eq!(st.get_pointer_field(3).which().unwrap(), Which::Other);
+}
+
+#[test]
+fn init_from_iter() {
+    let mut message = ::message::Builder::new_default();
+    {
+        let mut st = message.init_root::<Builder>().init_any_struct(0, 3);
+        st.borrow().get_pointer_field(0)
+            .init_from_iter::<::text_list::Builder, _>(vec!["foo", "bar"]);
+        st.borrow().get_pointer_field(1)
+            .init_from_iter::<::data_list::Builder, _>(vec![&[1u8, 2][..], &[]]);
+        st.borrow().get_pointer_field(2)
+            .init_from_iter::<::primitive_list::Builder<u64>, _>((0..5u32).map(|x| (x * x) as u64));
+    }
+
+    let st: ::any_struct::Reader = message.get_root_as_reader().unwrap();
+    let texts: ::text_list::Reader = st.get_pointer_field(0).get_as().unwrap();
+    assert_eq!(texts.iter().map(|t| t.unwrap()).collect::<Vec<_>>(), vec!["foo", "bar"]);
+    let datas: ::data_list::Reader = st.get_pointer_field(1).get_as().unwrap();
+    assert_eq!(datas.get(0).unwrap(), &[1, 2]);
+    assert_eq!(datas.get(1).unwrap().len(), 0);
+    let squares: ::primitive_list::Reader<u64> = st.get_pointer_field(2).get_as().unwrap();
+    assert_eq!(squares.iter().collect::<Vec<_>>(), vec![0, 1, 4, 9, 16]);
+
+    let list: ::primitive_list::Builder<u8> = message.init_root_from_iter(b"hello".iter().cloned());
+    assert_eq!(list.len(), 5);
+}
+
+/// A capability without methods, told apart from others by its number.
+#[cfg(test)]
+struct TestCap(usize);
+
+#[cfg(test)]
+impl TestCap {
+    fn no_methods() -> ::Error {
+        ::Error::unimplemented("TestCap has no methods.".to_string())
+    }
+}
+
+#[cfg(test)]
+impl ClientHook for TestCap {
+    fn add_ref(&self) -> Box<ClientHook> { Box::new(TestCap(self.0)) }
+    fn new_call(&self, interface_id: u64, method_id: u16, size_hint: Option<::MessageSize>)
+                -> ::capability::Request<Owned, Owned>
+    {
+        ::broken::new_cap(TestCap::no_methods()).new_call(interface_id, method_id, size_hint)
+    }
+    fn call(&self, _interface_id: u64, _method_id: u16,
+            _params: Box<::private::capability::ParamsHook>,
+            _results: Box<::private::capability::ResultsHook>)
+            -> ::capability::Promise<(), ::Error>
+    {
+        ::capability::Promise::err(TestCap::no_methods())
+    }
+    fn get_brand(&self) -> usize { 0 }
+    fn get_ptr(&self) -> usize { self.0 }
+    fn get_resolved(&self) -> Option<Box<ClientHook>> { None }
+    fn when_more_resolved(&self) -> Option<::capability::Promise<Box<ClientHook>, ::Error>> { None }
+}
+
+#[test]
+fn set_with_cap_translator() {
+    let mut source = ::message::Builder::new_default();
+    {
+        let mut st = source.init_root::<Builder>().init_any_struct(0, 3);
+        st.borrow().get_pointer_field(0).set_as_capability(Box::new(TestCap(1)));
+        st.borrow().get_pointer_field(1).set_as_capability(Box::new(TestCap(2)));
+        st.borrow().get_pointer_field(2).set_as("not a cap").unwrap();
+    }
+    let source_root: Reader = source.get_root_as_reader().unwrap();
+
+    let mut message = ::message::Builder::new_default();
+    let mut seen = Vec::new();
+    message.init_root::<Builder>().set_with_cap_translator(source_root, |cap| {
+        seen.push(cap.get_ptr());
+        if cap.get_ptr() == 1 {
+            Ok(None)
+        } else {
+            Ok(Some(Box::new(TestCap(cap.get_ptr() + 100)) as Box<ClientHook>))
+        }
+    }).unwrap();
+    assert_eq!(seen, vec![1, 2]);
+
+    {
+        let st: ::any_struct::Reader = message.get_root_as_reader().unwrap();
+        assert!(st.get_pointer_field(0).is_null());
+        assert_eq!(st.get_pointer_field(1).get_pipelined_cap(&[]).unwrap().get_ptr(), 102);
+        assert_eq!(st.get_pointer_field(2).get_as::<::text::Reader>().unwrap(), "not a cap");
+    }
+
+    // The translator fails on the second capability, after the struct and the first
+    // capability have been copied. None of that is left behind.
+    let result = message.init_root::<Builder>().set_with_cap_translator(source_root, |cap| {
+        if cap.get_ptr() == 1 {
+            Ok(Some(cap))
+        } else {
+            Err(::Error::failed("capabilities may not cross".to_string()))
+        }
+    });
+    assert!(result.is_err());
+    assert!(message.get_root_as_reader::<Reader>().unwrap().is_null());
+    assert!(message.get_segments_for_output().iter()
+            .all(|segment| segment.iter().skip(1).all(|word| *word == ::Word::from(0))));
+}
+
+#[test]
+fn read_message_with_cap_table() {
+    let mut builder = ::message::Builder::new_default();
+    {
+        let mut st = builder.init_root::<Builder>().init_any_struct(0, 2);
+        st.borrow().get_pointer_field(0).set_as_capability(Box::new(TestCap(1)));
+        st.borrow().get_pointer_field(1).set_as_capability(Box::new(TestCap(2)));
+    }
+    assert_eq!(builder.get_cap_table().iter().map(|cap| cap.as_ref().unwrap().get_ptr())
+                      .collect::<Vec<_>>(), vec![1, 2]);
+    builder.get_cap_table_mut()[0] = Some(Box::new(TestCap(10)));
+
+    let mut words = Vec::new();
+    ::serialize::write_message(&mut words, &builder).unwrap();
+    let reader = ::serialize::read_message(&mut &words[..], ::message::ReaderOptions::new())
+        .unwrap();
+    {
+        let st: ::any_struct::Reader = reader.get_root().unwrap();
+        assert!(st.get_pointer_field(0).get_pipelined_cap(&[]).is_err());
+    }
+
+    let reader = reader.with_cap_table(builder.get_cap_table().to_vec());
+    assert_eq!(reader.get_cap_table().len(), 2);
+    let st: ::any_struct::Reader = reader.get_root().unwrap();
+    assert_eq!(st.get_pointer_field(0).get_pipelined_cap(&[]).unwrap().get_ptr(), 10);
+    assert_eq!(st.get_pointer_field(1).get_pipelined_cap(&[]).unwrap().get_ptr(), 2);
+
+    let (reader, cap_table) = reader.into_parts();
+    assert_eq!(cap_table.len(), 2);
+    let st: ::any_struct::Reader = reader.get_root().unwrap();
+    assert!(st.get_pointer_field(0).get_pipelined_cap(&[]).is_err());
+}
@@ -0,0 +1,187 @@
+// Copyright (c) 2013-2015 Sandstorm Development Group, Inc. and contributors
+// Licensed under the MIT License:
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! A struct of unknown type.
+//!
+//! Useful for code that stores or forwards struct payloads without knowing their schema.
+//! `init_as()` has no way to know a size, so it initializes an empty struct; to give the new
+//! struct data and pointer sections, use `any_pointer::Builder::init_any_struct()` instead.
+
+use private::layout::{PointerBuilder, PointerReader, StructBuilder, StructReader, StructSize};
+use traits::{FromPointerReader, FromPointerBuilder, IndexMove, ListIter};
+use {MessageSize, Result};
+
+#[derive(Copy, Clone)]
+pub struct Owned;
+
+impl <'a> ::traits::Owned<'a> for Owned {
+    type Reader = Reader<'a>;
+    type Builder = Builder<'a>;
+}
+
+impl <'a> ::traits::OwnedStruct<'a> for Owned {
+    type Reader = Reader<'a>;
+    type Builder = Builder<'a>;
+}
+
+#[derive(Clone, Copy)]
+pub struct Reader<'a> {
+    reader: StructReader<'a>
+}
+
+impl <'a> Reader<'a> {
+    pub fn new(reader: StructReader<'a>) -> Reader<'a> {
+        Reader { reader: reader }
+    }
+
+    pub fn total_size(&self) -> Result<MessageSize> {
+        self.reader.total_size()
+    }
+
+    /// The raw bytes of the data section.
+    pub fn get_data_section(&self) -> &'a [u8] {
+        self.reader.get_data_section_as_blob()
+    }
+
+    pub fn get_pointer_section_size(&self) -> u16 {
+        self.reader.get_pointer_section_size()
+    }
+
+    /// Gets the pointer at `index` in the pointer section. Pointers past the end of the section
+    /// read as null, as they do for fields added in a newer version of a schema.
+    pub fn get_pointer_field(&self, index: u16) -> ::any_pointer::Reader<'a> {
+        ::any_pointer::Reader::new(self.reader.get_pointer_field(index as usize))
+    }
+
+    pub fn iter_pointers(self) -> ListIter<Reader<'a>, ::any_pointer::Reader<'a>> {
+        let size = self.get_pointer_section_size() as u32;
+        ListIter::new(self, size)
+    }
+}
+
+impl <'a> IndexMove<u32, ::any_pointer::Reader<'a>> for Reader<'a> {
+    fn index_move(&self, index: u32) -> ::any_pointer::Reader<'a> {
+        self.get_pointer_field(index as u16)
+    }
+}
+
+impl <'a> FromPointerReader<'a> for Reader<'a> {
+    fn get_from_pointer(reader: &PointerReader<'a>) -> Result<Reader<'a>> {
+        Ok(Reader { reader: try!(reader.get_struct(::std::ptr::null())) })
+    }
+}
+
+impl <'a> ::traits::FromStructReader<'a> for Reader<'a> {
+    fn new(reader: StructReader<'a>) -> Reader<'a> {
+        Reader { reader: reader }
+    }
+}
+
+impl <'a> ::traits::IntoInternalStructReader<'a> for Reader<'a> {
+    fn into_internal_struct_reader(self) -> StructReader<'a> {
+        self.reader
+    }
+}
+
+impl <'a> ::traits::SetPointerBuilder<Builder<'a>> for Reader<'a> {
+    fn set_pointer_builder<'b>(pointer: PointerBuilder<'b>, value: Reader<'a>) -> Result<()> {
+        pointer.set_struct(&value.reader)
+    }
+}
+
+pub struct Builder<'a> {
+    builder: StructBuilder<'a>
+}
+
+impl <'a> Builder<'a> {
+    pub fn new(builder: StructBuilder<'a>) -> Builder<'a> {
+        Builder { builder: builder }
+    }
+
+    pub fn as_reader(self) -> Reader<'a> {
+        Reader { reader: self.builder.as_reader() }
+    }
+
+    pub fn borrow<'b>(&'b mut self) -> Builder<'b> {
+        Builder { builder: self.builder }
+    }
+
+    pub fn total_size(&self) -> Result<MessageSize> {
+        self.builder.as_reader().total_size()
+    }
+
+    /// The raw bytes of the data section.
+    pub fn get_data_section(self) -> &'a mut [u8] {
+        self.builder.get_data_section_mut()
+    }
+
+    pub fn get_pointer_section_size(&self) -> u16 {
+        self.builder.get_pointer_section_size()
+    }
+
+    /// Overwrites this struct with the contents of `other`, dropping whatever does not fit
+    /// and zeroing whatever `other` lacks.
+    pub fn copy_content_from(&mut self, other: &Reader) -> Result<()> {
+        self.builder.copy_content_from(&other.reader)
+    }
+
+    /// Gets the pointer at `index` in the pointer section. Panics if `index` is out of range.
+    pub fn get_pointer_field(self, index: u16) -> ::any_pointer::Builder<'a> {
+        assert!(index < self.builder.get_pointer_section_size());
+        ::any_pointer::Builder::new(self.builder.get_pointer_field(index as usize))
+    }
+
+    pub fn iter_pointers(self) -> ListIter<Builder<'a>, ::any_pointer::Builder<'a>> {
+        let size = self.get_pointer_section_size() as u32;
+        ListIter::new(self, size)
+    }
+}
+
+impl <'a> IndexMove<u32, ::any_pointer::Builder<'a>> for Builder<'a> {
+    fn index_move(&self, index: u32) -> ::any_pointer::Builder<'a> {
+        ::any_pointer::Builder::new(self.builder.get_pointer_field(index as usize))
+    }
+}
+
+impl <'a> ::traits::FromStructBuilder<'a> for Builder<'a> {
+    fn new(builder: StructBuilder<'a>) -> Builder<'a> {
+        Builder { builder: builder }
+    }
+}
+
+/// Like `init_as()`, a struct list of unknown structs has no way to know a size, so its new
+/// elements are empty structs.
+impl <'a> ::traits::HasStructSize for Builder<'a> {
+    fn struct_size() -> StructSize { StructSize { data: 0, pointers: 0 } }
+}
+
+impl <'a> FromPointerBuilder<'a> for Builder<'a> {
+    fn init_pointer(builder: PointerBuilder<'a>, _size: u32) -> Builder<'a> {
+        Builder { builder: builder.init_struct(StructSize { data: 0, pointers: 0 }) }
+    }
+
+    fn get_from_pointer(builder: PointerBuilder<'a>) -> Result<Builder<'a>> {
+        // An existing struct is always at least as large as the empty struct,
+        // so this never needs to upgrade anything.
+        let size = StructSize { data: 0, pointers: 0 };
+        Ok(Builder { builder: try!(builder.get_struct(size, ::std::ptr::null())) })
+    }
+}
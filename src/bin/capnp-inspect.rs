@@ -0,0 +1,585 @@
+// Copyright (c) 2013-2017 Sandstorm Development Group, Inc. and contributors
+// Licensed under the MIT License:
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Command-line tool for looking at serialized Cap'n Proto messages without a schema.
+//!
+//! ```text
+//! capnp-inspect [dump] [OPTIONS] [FILE]    validate a message and print its structure
+//! capnp-inspect pack [OPTIONS] [FILE]      re-encode a message using the packed encoding
+//! capnp-inspect unpack [OPTIONS] [FILE]    re-encode a message using the standard framing
+//! ```
+//!
+//! Messages are read from `FILE`, or from standard input if `FILE` is absent or `-`.
+
+extern crate byteorder;
+extern crate capnp;
+
+use std::cmp;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process;
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use capnp::{any_list, any_pointer, any_struct, data, message, primitive_list, serialize,
+            serialize_packed, Word};
+use capnp::any_list::ElementSize;
+use capnp::any_pointer::Which;
+use capnp::message::ReaderSegments;
+
+const USAGE: &'static str = "\
+Usage:
+    capnp-inspect [dump] [OPTIONS] [FILE]
+    capnp-inspect pack [OPTIONS] [FILE]
+    capnp-inspect unpack [OPTIONS] [FILE]
+
+Reads a Cap'n Proto message from FILE (or standard input if FILE is absent or `-`),
+validates it, and then either prints its structure (`dump`, the default) or
+re-encodes it (`pack`, `unpack`).
+
+Options:
+    --packed               The input uses the packed encoding.
+    --unpacked             The input uses the standard (unpacked) stream framing.
+                           If neither is given, the encoding is auto-detected.
+    --traversal-limit N    Traversal limit in words (default: 8388608).
+    --nesting-limit N      Nesting limit (default: 64).
+    --max-elements N       Maximum number of list elements to print per list (default: 16).
+    -o, --output FILE      Where to write the output (default: stdout).
+    -h, --help             Print this message.
+";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Command { Dump, Pack, Unpack }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Encoding { Unpacked, Packed, Auto }
+
+struct Options {
+    command: Command,
+    encoding: Encoding,
+    reader_options: message::ReaderOptions,
+    max_elements: u32,
+    input: Option<String>,
+    output: Option<String>,
+}
+
+fn usage_error(message: String) -> ! {
+    let _ = write!(io::stderr(), "error: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+fn fail(message: String) -> ! {
+    let _ = writeln!(io::stderr(), "error: {}", message);
+    process::exit(1);
+}
+
+fn parse_number<T: ::std::str::FromStr>(flag: &str, value: Option<String>) -> T {
+    match value {
+        None => usage_error(format!("{} requires an argument", flag)),
+        Some(v) => match v.parse() {
+            Ok(n) => n,
+            Err(_) => usage_error(format!("invalid value for {}: {}", flag, v)),
+        }
+    }
+}
+
+fn parse_args<I>(args: I) -> Options where I: Iterator<Item=String> {
+    let mut options = Options {
+        command: Command::Dump,
+        encoding: Encoding::Auto,
+        reader_options: message::ReaderOptions::new(),
+        max_elements: 16,
+        input: None,
+        output: None,
+    };
+
+    let mut args = args.peekable();
+    match args.peek().map(|s| &s[..]) {
+        Some("dump") => { options.command = Command::Dump; }
+        Some("pack") => { options.command = Command::Pack; }
+        Some("unpack") => { options.command = Command::Unpack; }
+        _ => (),
+    }
+    if options.command != Command::Dump || args.peek().map(|s| &s[..]) == Some("dump") {
+        args.next();
+    }
+
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                process::exit(0);
+            }
+            "--packed" => options.encoding = Encoding::Packed,
+            "--unpacked" => options.encoding = Encoding::Unpacked,
+            "--traversal-limit" => {
+                let limit = parse_number(&arg, args.next());
+                options.reader_options.traversal_limit_in_words(limit);
+            }
+            "--nesting-limit" => {
+                let limit = parse_number(&arg, args.next());
+                options.reader_options.nesting_limit(limit);
+            }
+            "--max-elements" => options.max_elements = parse_number(&arg, args.next()),
+            "-o" | "--output" => match args.next() {
+                None => usage_error(format!("{} requires an argument", arg)),
+                Some(path) => options.output = Some(path),
+            },
+            _ if arg.starts_with('-') && arg != "-" => {
+                usage_error(format!("unrecognized option: {}", arg))
+            }
+            _ => {
+                if options.input.is_some() {
+                    usage_error(format!("unexpected argument: {}", arg))
+                }
+                options.input = Some(arg);
+            }
+        }
+    }
+    options
+}
+
+fn read_input(input: &Option<String>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let result = match *input {
+        Some(ref path) if path != "-" => File::open(path).and_then(|mut f| f.read_to_end(&mut bytes)),
+        _ => io::stdin().read_to_end(&mut bytes),
+    };
+    match result {
+        Ok(_) => bytes,
+        Err(e) => fail(format!("failed to read input: {}", e)),
+    }
+}
+
+/// Returns the number of bytes that `bytes` would need to hold, if it were a message
+/// in the standard framing. Returns `None` if the segment table is obviously invalid.
+fn unpacked_message_length(bytes: &[u8]) -> Option<u64> {
+    if bytes.len() < 8 { return None }
+    let segment_count = LittleEndian::read_u32(&bytes[0..4]) as u64 + 1;
+    if segment_count >= 512 { return None }
+    let table_words = segment_count / 2 + 1;
+    if (bytes.len() as u64) < table_words * 8 { return None }
+    let mut total_words = table_words;
+    for idx in 0..segment_count {
+        let offset = 4 + 4 * idx as usize;
+        total_words += LittleEndian::read_u32(&bytes[offset..offset + 4]) as u64;
+    }
+    Some(total_words * 8)
+}
+
+fn detect_encoding(bytes: &[u8], requested: Encoding) -> Encoding {
+    if requested != Encoding::Auto {
+        return requested;
+    }
+    match unpacked_message_length(bytes) {
+        Some(length) if length == bytes.len() as u64 => Encoding::Unpacked,
+        _ => Encoding::Packed,
+    }
+}
+
+fn read_message(bytes: &[u8], encoding: Encoding, options: message::ReaderOptions)
+                -> capnp::Result<serialize::OwnedSegments>
+{
+    let reader = match encoding {
+        Encoding::Packed => try!(serialize_packed::read_message(&mut &bytes[..], options)),
+        _ => {
+            let mut read = bytes;
+            let reader = try!(serialize::read_message(&mut read, options));
+            if !read.is_empty() {
+                return Err(capnp::Error::failed(
+                    format!("{} trailing bytes after the end of the message", read.len())));
+            }
+            reader
+        }
+    };
+    Ok(reader.into_segments())
+}
+
+fn collect_segments(segments: &serialize::OwnedSegments) -> Vec<&[Word]> {
+    let mut result = Vec::new();
+    while let Some(segment) = segments.get_segment(result.len() as u32) {
+        result.push(segment);
+    }
+    result
+}
+
+/// Checks that the message can be traversed under the given options, returning its size.
+fn validate(segments: &[&[Word]], options: message::ReaderOptions)
+            -> capnp::Result<capnp::MessageSize>
+{
+    let reader = message::Reader::new(message::SegmentArray::new(segments), options);
+    let root: any_pointer::Reader = try!(reader.get_root());
+    root.total_size()
+}
+
+fn element_size_name(element_size: ElementSize) -> &'static str {
+    match element_size {
+        ElementSize::Void => "void",
+        ElementSize::Bit => "bit",
+        ElementSize::Byte => "byte",
+        ElementSize::TwoBytes => "two-byte",
+        ElementSize::FourBytes => "four-byte",
+        ElementSize::EightBytes => "eight-byte",
+        ElementSize::Pointer => "pointer",
+        ElementSize::InlineComposite => "inline-composite",
+    }
+}
+
+/// Prints the objects reachable from a pointer. Decode errors are printed in place of the
+/// object they concern, so that the rest of the message can still be looked at.
+struct Dumper<'a, W> where W: Write + 'a {
+    out: &'a mut W,
+    max_elements: u32,
+}
+
+impl <'a, W> Dumper<'a, W> where W: Write {
+    fn indent(&mut self, depth: usize) -> io::Result<()> {
+        for _ in 0..depth {
+            try!(self.out.write_all(b"  "));
+        }
+        Ok(())
+    }
+
+    fn dump_error(&mut self, error: capnp::Error, depth: usize) -> io::Result<()> {
+        try!(self.indent(depth));
+        writeln!(self.out, "<{}>", error)
+    }
+
+    fn dump_pointer(&mut self, label: &str, pointer: any_pointer::Reader, depth: usize)
+                    -> io::Result<()>
+    {
+        try!(self.indent(depth));
+        try!(write!(self.out, "{}: ", label));
+        match pointer.which() {
+            Err(e) => writeln!(self.out, "<{}>", e),
+            Ok(Which::Null) => writeln!(self.out, "null"),
+            Ok(Which::Capability { index }) => writeln!(self.out, "capability {}", index),
+            Ok(Which::Other) => writeln!(self.out, "<unknown pointer kind>"),
+            Ok(Which::Struct { data_words, pointer_count }) => {
+                try!(writeln!(self.out, "struct ({} data words, {} pointers)",
+                              data_words, pointer_count));
+                match pointer.get_as() {
+                    Ok(reader) => self.dump_struct_body(reader, depth + 1),
+                    Err(e) => self.dump_error(e, depth + 1),
+                }
+            }
+            Ok(Which::List { element_size: ElementSize::InlineComposite, element_count }) => {
+                try!(writeln!(self.out, "list of {} structs", element_count));
+                match pointer.get_as() {
+                    Ok(reader) => self.dump_list_body(pointer, reader, depth + 1),
+                    Err(e) => self.dump_error(e, depth + 1),
+                }
+            }
+            Ok(Which::List { element_size, element_count }) => {
+                try!(writeln!(self.out, "list of {} {} elements",
+                              element_count, element_size_name(element_size)));
+                match pointer.get_as() {
+                    Ok(reader) => self.dump_list_body(pointer, reader, depth + 1),
+                    Err(e) => self.dump_error(e, depth + 1),
+                }
+            }
+        }
+    }
+
+    fn dump_struct_body(&mut self, reader: any_struct::Reader, depth: usize) -> io::Result<()> {
+        for (idx, word) in reader.get_data_section().chunks(8).enumerate() {
+            let value = word.iter().rev().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+            try!(self.indent(depth));
+            try!(writeln!(self.out, "data word {}: {:#018x}", idx, value));
+        }
+        for (idx, pointer) in reader.iter_pointers().enumerate() {
+            try!(self.dump_pointer(&format!("pointer {}", idx), pointer, depth));
+        }
+        Ok(())
+    }
+
+    fn dump_list_body(&mut self, pointer: any_pointer::Reader, list: any_list::Reader,
+                      depth: usize) -> io::Result<()>
+    {
+        let shown = cmp::min(list.len(), self.max_elements);
+        match list.get_element_size() {
+            ElementSize::Void => return Ok(()),
+            ElementSize::Byte => {
+                return match pointer.get_as::<data::Reader>() {
+                    Ok(bytes) => self.dump_bytes(bytes, depth),
+                    Err(e) => self.dump_error(e, depth),
+                }
+            }
+            ElementSize::Bit => match pointer.get_as::<primitive_list::Reader<bool>>() {
+                Ok(values) => try!(self.dump_values(shown, depth, |idx| values.get(idx) as u64)),
+                Err(e) => return self.dump_error(e, depth),
+            },
+            ElementSize::TwoBytes => match pointer.get_as::<primitive_list::Reader<u16>>() {
+                Ok(values) => try!(self.dump_values(shown, depth, |idx| values.get(idx) as u64)),
+                Err(e) => return self.dump_error(e, depth),
+            },
+            ElementSize::FourBytes => match pointer.get_as::<primitive_list::Reader<u32>>() {
+                Ok(values) => try!(self.dump_values(shown, depth, |idx| values.get(idx) as u64)),
+                Err(e) => return self.dump_error(e, depth),
+            },
+            ElementSize::EightBytes => match pointer.get_as::<primitive_list::Reader<u64>>() {
+                Ok(values) => try!(self.dump_values(shown, depth, |idx| values.get(idx))),
+                Err(e) => return self.dump_error(e, depth),
+            },
+            ElementSize::Pointer => {
+                for idx in 0..shown {
+                    try!(self.dump_pointer(&format!("element {}", idx),
+                                           list.get_pointer_element(idx), depth));
+                }
+            }
+            ElementSize::InlineComposite => {
+                for idx in 0..shown {
+                    try!(self.indent(depth));
+                    try!(writeln!(self.out, "element {}:", idx));
+                    try!(self.dump_struct_body(list.get_struct_element(idx), depth + 1));
+                }
+            }
+        }
+        if shown < list.len() {
+            try!(self.indent(depth));
+            try!(writeln!(self.out, "... ({} more)", list.len() - shown));
+        }
+        Ok(())
+    }
+
+    fn dump_values<F>(&mut self, count: u32, depth: usize, get: F) -> io::Result<()>
+        where F: Fn(u32) -> u64
+    {
+        for idx in 0..count {
+            try!(self.indent(depth));
+            try!(writeln!(self.out, "element {}: {:#x}", idx, get(idx)));
+        }
+        Ok(())
+    }
+
+    fn dump_bytes(&mut self, bytes: &[u8], depth: usize) -> io::Result<()> {
+        try!(self.indent(depth));
+        let as_text = match bytes.split_last() {
+            Some((&0, text)) => ::std::str::from_utf8(text).ok(),
+            _ => None,
+        };
+        match as_text {
+            Some(text) => writeln!(self.out, "text: {:?}", text),
+            None => {
+                let shown = cmp::min(bytes.len(), self.max_elements as usize * 8);
+                try!(write!(self.out, "bytes:"));
+                for b in &bytes[..shown] {
+                    try!(write!(self.out, " {:02x}", b));
+                }
+                if shown < bytes.len() {
+                    try!(write!(self.out, " ..."));
+                }
+                writeln!(self.out)
+            }
+        }
+    }
+}
+
+fn dump<W>(options: &Options, encoding: Encoding, segments: &[&[Word]], out: &mut W)
+           -> io::Result<bool> where W: Write
+{
+    try!(writeln!(out, "encoding: {}", match encoding {
+        Encoding::Packed => "packed",
+        _ => "unpacked",
+    }));
+    try!(writeln!(out, "segments: {}", segments.len()));
+    let mut total_words = 0;
+    for (idx, segment) in segments.iter().enumerate() {
+        try!(writeln!(out, "  segment {}: {} words", idx, segment.len()));
+        total_words += segment.len();
+    }
+    try!(writeln!(out, "total segment words: {}", total_words));
+
+    let size = match validate(segments, options.reader_options) {
+        Ok(size) => size,
+        Err(e) => {
+            try!(writeln!(out, "invalid message: {}", e));
+            return Ok(false);
+        }
+    };
+    try!(writeln!(out, "total_size: {} words, {} capabilities", size.word_count, size.cap_count));
+
+    let message = message::Reader::new(message::SegmentArray::new(segments), options.reader_options);
+    let mut dumper = Dumper { out: out, max_elements: options.max_elements };
+    match message.get_root() {
+        Ok(root) => try!(dumper.dump_pointer("root", root, 0)),
+        Err(e) => try!(dumper.dump_error(e, 0)),
+    }
+    Ok(true)
+}
+
+/// Runs `dump`, `pack` or `unpack` on a message, writing the result to `out`. Returns
+/// false if the message is invalid.
+fn inspect<W>(options: &Options, input: &[u8], out: &mut W) -> io::Result<bool> where W: Write {
+    let encoding = detect_encoding(input, options.encoding);
+    let owned_segments = match read_message(input, encoding, options.reader_options) {
+        Ok(segments) => segments,
+        Err(e) => {
+            let _ = writeln!(io::stderr(), "error: failed to read message: {}", e);
+            return Ok(false);
+        }
+    };
+    let segments = collect_segments(&owned_segments);
+    if options.command == Command::Dump {
+        return dump(options, encoding, &segments, out);
+    }
+    if let Err(e) = validate(&segments, options.reader_options) {
+        let _ = writeln!(io::stderr(), "error: invalid message: {}", e);
+        return Ok(false);
+    }
+    if options.command == Command::Pack {
+        try!(serialize_packed::write_message_segments(out, &owned_segments));
+    } else {
+        try!(serialize::write_message_segments(out, &owned_segments));
+    }
+    Ok(true)
+}
+
+fn run<W>(options: &Options, mut out: W) -> io::Result<bool> where W: Write {
+    let input = read_input(&options.input);
+    let ok = try!(inspect(options, &input, &mut out));
+    try!(out.flush());
+    Ok(ok)
+}
+
+fn main() {
+    let options = parse_args(::std::env::args().skip(1));
+
+    let stdout = io::stdout();
+    let result = match options.output {
+        Some(ref path) if path != "-" => match File::create(path) {
+            Ok(file) => run(&options, io::BufWriter::new(file)),
+            Err(e) => fail(format!("failed to create {}: {}", path, e)),
+        },
+        _ => run(&options, io::BufWriter::new(stdout.lock())),
+    };
+
+    match result {
+        Ok(true) => (),
+        Ok(false) => process::exit(1),
+        Err(ref e) if e.kind() == io::ErrorKind::BrokenPipe => (),
+        Err(e) => fail(format!("{}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use capnp::{any_pointer, message, primitive_list, serialize, serialize_packed, text};
+    use super::{detect_encoding, inspect, parse_args, Command, Encoding, Options};
+
+    fn options(args: &[&str]) -> Options {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn sample_message(first_segment_words: u32) -> Vec<u8> {
+        let allocator = message::HeapAllocator::new().first_segment_words(first_segment_words);
+        let mut message = message::Builder::new(allocator);
+        {
+            let mut root = message.init_root::<any_pointer::Builder>().init_any_struct(1, 3);
+            root.borrow().get_data_section()[0] = 42;
+            root.borrow().get_pointer_field(0).set_as::<text::Builder, _>("hi").unwrap();
+            let mut list: primitive_list::Builder<u16> =
+                root.borrow().get_pointer_field(1).initn_as(20);
+            for idx in 0..20 {
+                list.set(idx, idx as u16 + 1);
+            }
+        }
+        let mut bytes = Vec::new();
+        serialize::write_message(&mut bytes, &message).unwrap();
+        bytes
+    }
+
+    fn run_inspect(args: &[&str], input: &[u8]) -> (bool, Vec<u8>) {
+        let mut out = Vec::new();
+        let ok = inspect(&options(args), input, &mut out).unwrap();
+        (ok, out)
+    }
+
+    #[test]
+    fn parse_commands() {
+        let dump = options(&["--packed", "--max-elements", "3", "in.bin"]);
+        assert_eq!(dump.command, Command::Dump);
+        assert_eq!(dump.encoding, Encoding::Packed);
+        assert_eq!(dump.max_elements, 3);
+        assert_eq!(dump.input, Some("in.bin".to_string()));
+
+        let pack = options(&["pack", "-o", "out.bin"]);
+        assert_eq!(pack.command, Command::Pack);
+        assert_eq!(pack.input, None);
+        assert_eq!(pack.output, Some("out.bin".to_string()));
+    }
+
+    #[test]
+    fn detect_packed_and_unpacked() {
+        let unpacked = sample_message(1024);
+        let (_, packed) = run_inspect(&["pack"], &unpacked);
+        assert_eq!(detect_encoding(&unpacked, Encoding::Auto), Encoding::Unpacked);
+        assert_eq!(detect_encoding(&packed, Encoding::Auto), Encoding::Packed);
+        assert_eq!(detect_encoding(&packed, Encoding::Unpacked), Encoding::Unpacked);
+    }
+
+    #[test]
+    fn dump_follows_far_pointers() {
+        // With a one-word first segment, the root pointer is a far pointer.
+        let (ok, out) = run_inspect(&["dump", "--max-elements", "2"], &sample_message(1));
+        assert!(ok);
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("encoding: unpacked\n"), "{}", out);
+        assert!(out.contains("\n  segment 0: 1 words\n"), "{}", out);
+        assert!(out.ends_with("\
+root: struct (1 data words, 3 pointers)
+  data word 0: 0x000000000000002a
+  pointer 0: list of 3 byte elements
+    text: \"hi\"
+  pointer 1: list of 20 two-byte elements
+    element 0: 0x1
+    element 1: 0x2
+    ... (18 more)
+  pointer 2: null
+"), "{}", out);
+    }
+
+    #[test]
+    fn reject_invalid_message() {
+        let (ok, out) = run_inspect(&["--traversal-limit", "2"], &sample_message(1024));
+        assert!(!ok);
+        assert!(out.is_empty());
+        let (ok, out) = run_inspect(&["pack"], &[1, 2, 3]);
+        assert!(!ok);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn pack_and_unpack() {
+        let unpacked = sample_message(1);
+        let (ok, packed) = run_inspect(&["pack"], &unpacked);
+        assert!(ok);
+        assert!(packed.len() < unpacked.len());
+        let (ok, roundtrip) = run_inspect(&["unpack"], &packed);
+        assert!(ok);
+        assert_eq!(roundtrip, unpacked);
+
+        let message = serialize_packed::read_message(&mut &packed[..],
+                                                     message::ReaderOptions::new()).unwrap();
+        let root: any_pointer::Reader = message.get_root().unwrap();
+        assert_eq!(root.total_size().unwrap().word_count, 1 + 3 + 1 + 5);
+    }
+}
@@ -0,0 +1,192 @@
+// Copyright (c) 2013-2015 Sandstorm Development Group, Inc. and contributors
+// Licensed under the MIT License:
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Capabilities that fail every call made on them.
+
+use std::rc::Rc;
+
+use any_pointer;
+use capability::{Promise, RemotePromise};
+use message::{self, HeapAllocator};
+use private::capability::{ClientHook, ParamsHook, PipelineHook, PipelineOp, RequestHook,
+                          ResultsHook};
+use {Error, MessageSize};
+
+/// Returns a capability whose calls all fail with `error`.
+pub fn new_cap(error: Error) -> Box<ClientHook> {
+    Box::new(Client { inner: Rc::new(Inner { error: error, is_null: false }) })
+}
+
+/// Returns the capability that a null capability pointer stands for. Its calls all fail,
+/// and `ClientHook::is_null()` returns true for it.
+pub fn new_null_cap() -> Box<ClientHook> {
+    Box::new(Client {
+        inner: Rc::new(Inner {
+            error: Error::failed("Called null capability.".to_string()),
+            is_null: true,
+        }),
+    })
+}
+
+struct Inner {
+    error: Error,
+    is_null: bool,
+}
+
+/// Shares its `Inner` with the references that `add_ref()` hands out, so that they all have
+/// the same `get_ptr()`.
+struct Client {
+    inner: Rc<Inner>,
+}
+
+impl ClientHook for Client {
+    fn add_ref(&self) -> Box<ClientHook> {
+        Box::new(Client { inner: self.inner.clone() })
+    }
+
+    fn new_call(&self, _interface_id: u64, _method_id: u16, size_hint: Option<MessageSize>)
+                -> ::capability::Request<any_pointer::Owned, any_pointer::Owned>
+    {
+        let message = ::private::capability::new_message(size_hint);
+        ::capability::Request::new(
+            Box::new(Request { message: message, error: self.inner.error.clone() }))
+    }
+
+    fn call(&self, _interface_id: u64, _method_id: u16,
+            _params: Box<ParamsHook>, _results: Box<ResultsHook>) -> Promise<(), Error>
+    {
+        Promise::err(self.inner.error.clone())
+    }
+
+    fn get_brand(&self) -> usize {
+        0
+    }
+
+    fn get_ptr(&self) -> usize {
+        &*self.inner as *const _ as usize
+    }
+
+    fn get_resolved(&self) -> Option<Box<ClientHook>> {
+        None
+    }
+
+    fn when_more_resolved(&self) -> Option<Promise<Box<ClientHook>, Error>> {
+        None
+    }
+
+    fn is_null(&self) -> bool {
+        self.inner.is_null
+    }
+}
+
+/// A call on a broken capability. Its params can be filled in as usual, but sending it fails.
+struct Request {
+    message: message::Builder<HeapAllocator>,
+    error: Error,
+}
+
+impl RequestHook for Request {
+    fn get<'a>(&'a mut self) -> any_pointer::Builder<'a> {
+        self.message.get_root().unwrap()
+    }
+
+    fn get_brand(&self) -> usize {
+        0
+    }
+
+    fn send<'a>(self: Box<Self>) -> RemotePromise<any_pointer::Owned> {
+        let pipeline = Pipeline { error: self.error.clone() };
+        RemotePromise {
+            promise: Promise::err(self.error),
+            pipeline: any_pointer::Pipeline::new(Box::new(pipeline)),
+        }
+    }
+
+    fn tail_send(self: Box<Self>)
+                 -> Option<(u32, Promise<(), Error>, Box<PipelineHook>)>
+    {
+        None
+    }
+}
+
+struct Pipeline {
+    error: Error,
+}
+
+impl PipelineHook for Pipeline {
+    fn add_ref(&self) -> Box<PipelineHook> {
+        Box::new(Pipeline { error: self.error.clone() })
+    }
+
+    fn get_pipelined_cap(&self, _ops: &[PipelineOp]) -> Box<ClientHook> {
+        new_cap(self.error.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use any_pointer;
+    use Error;
+
+    #[test]
+    fn null_capability_pointer() {
+        let mut message = ::message::Builder::new_default();
+        {
+            let mut st = message.init_root::<any_pointer::Builder>().init_any_struct(0, 2);
+            st.borrow().get_pointer_field(0).set_as_capability(super::new_null_cap());
+            st.borrow().get_pointer_field(1).set_as_capability(
+                super::new_cap(Error::overloaded("busy".to_string())));
+        }
+
+        let st: ::any_struct::Reader = message.get_root_as_reader().unwrap();
+        assert!(st.get_pointer_field(0).is_null());
+        assert!(st.get_pointer_field(0).get_pipelined_cap(&[]).unwrap().is_null());
+        assert!(!st.get_pointer_field(1).get_pipelined_cap(&[]).unwrap().is_null());
+    }
+
+    #[test]
+    fn references_share_identity() {
+        let cap = super::new_cap(Error::failed("broken".to_string()));
+        assert_eq!(cap.add_ref().get_ptr(), cap.get_ptr());
+        assert_eq!(cap.clone().get_ptr(), cap.get_ptr());
+        assert!(super::new_cap(Error::failed("broken".to_string())).get_ptr() != cap.get_ptr());
+
+        let null = super::new_null_cap();
+        assert_eq!(null.add_ref().get_ptr(), null.get_ptr());
+    }
+
+    #[cfg(feature = "rpc")]
+    #[test]
+    fn broken_calls_keep_error_kind() {
+        use futures::Future;
+        use ErrorKind;
+
+        let cap = super::new_cap(Error::overloaded("busy".to_string()));
+        let mut request = cap.new_call(0, 0, None);
+        request.get().set_as("ignored").unwrap();
+        let remote = request.hook.send();
+        let pipelined = remote.pipeline.get_pointer_field(0).as_cap();
+        assert_eq!(remote.promise.wait().err().unwrap().kind, ErrorKind::Overloaded);
+        let e = pipelined.new_call(0, 0, None).hook.send().promise.wait().err().unwrap();
+        assert_eq!(e.kind, ErrorKind::Overloaded);
+        assert_eq!(e.description, "busy");
+    }
+}
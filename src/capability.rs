@@ -30,10 +30,19 @@ use private::capability::{ClientHook, ParamsHook, RequestHook, ResponseHook, Res
 #[cfg(feature = "rpc")]
 use futures::Future;
 
+#[cfg(not(feature = "rpc"))]
+use std::cell::{Cell, RefCell};
 use std::marker::PhantomData;
+#[cfg(not(feature = "rpc"))]
+use std::rc::Rc;
 
 /// A computation that might eventually resolve to a value of type `T` or to an error
 ///  of type `E`. Dropping the promise cancels the computation.
+///
+/// Promises are combined with `join_all()`, and driven to completion by an `Executor`. With the
+/// `rpc` feature they are `futures::Future`s and are chained with its combinators; without it
+/// they have `map()`, `map_err()` and `then()` of their own. With the `std-future` feature they
+/// are also `std::future::Future`s, so that they can be awaited.
 #[must_use = "futures do nothing unless polled"]
 pub struct Promise<T, E> {
     #[allow(dead_code)]
@@ -46,10 +55,25 @@ enum PromiseInner<T, E> {
     #[cfg(feature = "rpc")]
     Deferred(Box<Future<Item=T,Error=E> + 'static>),
 
-    #[cfg(feature = "rpc")]
+    /// Returns the outcome once there is one.
+    #[cfg(not(feature = "rpc"))]
+    Deferred(Box<FnMut() -> Option<Result<T, E>> + 'static>),
+
     Empty,
 }
 
+// A promise never relies on staying in place, so it can be polled through `Pin<&mut Self>`.
+// Only `std::future::Future` needs this, so other builds still work on toolchains that
+// predate `Unpin`.
+#[cfg(feature = "std-future")]
+impl <T, E> ::std::marker::Unpin for Promise<T, E> {}
+
+impl <T, E> From<Result<T, E>> for Promise<T, E> {
+    fn from(result: Result<T, E>) -> Promise<T, E> {
+        Promise { inner: PromiseInner::Immediate(result) }
+    }
+}
+
 impl <T, E> Promise<T, E> {
     pub fn ok(value: T) -> Promise<T, E> {
         Promise { inner: PromiseInner::Immediate(Ok(value)) }
@@ -65,6 +89,110 @@ impl <T, E> Promise<T, E> {
     {
         Promise { inner: PromiseInner::Deferred(Box::new(f)) }
     }
+
+    #[cfg(not(feature = "rpc"))]
+    fn from_poll_fn<F>(f: F) -> Promise<T, E>
+        where F: FnMut() -> Option<Result<T, E>> + 'static
+    {
+        Promise { inner: PromiseInner::Deferred(Box::new(f)) }
+    }
+
+    /// Returns the outcome if there is one yet.
+    #[cfg(not(feature = "rpc"))]
+    fn poll_now(&mut self) -> Option<Result<T, E>> {
+        if let PromiseInner::Deferred(ref mut f) = self.inner {
+            return f();
+        }
+        match ::std::mem::replace(&mut self.inner, PromiseInner::Empty) {
+            PromiseInner::Immediate(result) => Some(result),
+            PromiseInner::Empty => panic!("Promise polled after done."),
+            PromiseInner::Deferred(_) => unreachable!(),
+        }
+    }
+}
+
+impl <T, E> Promise<T, E> where T: 'static, E: 'static {
+    // Under `rpc`, `futures::Future` provides `map()`, `map_err()` and `then()`. Inherent
+    // methods of the same names would take precedence over them and change what existing
+    // callers get, so these are only defined without it.
+
+    /// Transforms the value that the promise resolves to.
+    #[cfg(not(feature = "rpc"))]
+    pub fn map<U, F>(self, f: F) -> Promise<U, E>
+        where F: FnOnce(T) -> U + 'static, U: 'static
+    {
+        self.then(move |result| result.map(f))
+    }
+
+    /// Transforms the error that the promise fails with.
+    #[cfg(not(feature = "rpc"))]
+    pub fn map_err<E2, F>(self, f: F) -> Promise<T, E2>
+        where F: FnOnce(E) -> E2 + 'static, E2: 'static
+    {
+        self.then(move |result| result.map_err(f))
+    }
+
+
+    /// Runs `f` on the outcome of the promise, and continues with the promise or `Result` that
+    /// it returns.
+    #[cfg(not(feature = "rpc"))]
+    pub fn then<U, E2, R, F>(mut self, f: F) -> Promise<U, E2>
+        where F: FnOnce(Result<T, E>) -> R + 'static, R: Into<Promise<U, E2>>,
+              U: 'static, E2: 'static
+    {
+        let mut f = Some(f);
+        let mut next: Option<Promise<U, E2>> = None;
+        Promise::from_poll_fn(move || {
+            if next.is_none() {
+                match self.poll_now() {
+                    None => return None,
+                    Some(result) => {
+                        let f = f.take().expect("then() callback already run");
+                        next = Some(f(result).into());
+                    }
+                }
+            }
+            next.as_mut().map(|next| next.poll_now()).unwrap_or(None)
+        })
+    }
+
+    /// Waits for all of `promises`, and resolves to their values in order. Fails as soon as
+    /// one of them fails.
+    #[cfg(feature = "rpc")]
+    pub fn join_all<I>(promises: I) -> Promise<Vec<T>, E>
+        where I: IntoIterator<Item=Promise<T, E>>
+    {
+        let promises: Vec<Promise<T, E>> = promises.into_iter().collect();
+        Promise::from_future(::futures::future::join_all(promises))
+    }
+
+    /// Waits for all of `promises`, and resolves to their values in order. Fails as soon as
+    /// one of them fails.
+    #[cfg(not(feature = "rpc"))]
+    pub fn join_all<I>(promises: I) -> Promise<Vec<T>, E>
+        where I: IntoIterator<Item=Promise<T, E>>
+    {
+        let mut pending: Vec<Option<Promise<T, E>>> = promises.into_iter().map(Some).collect();
+        let mut values: Vec<Option<T>> = pending.iter().map(|_| None).collect();
+        Promise::from_poll_fn(move || {
+            for (promise, value) in pending.iter_mut().zip(values.iter_mut()) {
+                let result = match *promise {
+                    Some(ref mut promise) => promise.poll_now(),
+                    None => continue,
+                };
+                match result {
+                    None => (),
+                    Some(Ok(v)) => { *value = Some(v); *promise = None; }
+                    Some(Err(e)) => return Some(Err(e)),
+                }
+            }
+            if pending.iter().all(|promise| promise.is_none()) {
+                Some(Ok(values.iter_mut().map(|value| value.take().unwrap()).collect()))
+            } else {
+                None
+            }
+        })
+    }
 }
 
 #[cfg(feature = "rpc")]
@@ -88,6 +216,315 @@ impl <T, E> Future for Promise<T, E>
     }
 }
 
+#[cfg(all(feature = "std-future", feature = "rpc"))]
+struct WakerNotify(::std::task::Waker);
+
+#[cfg(all(feature = "std-future", feature = "rpc"))]
+impl ::futures::executor::Notify for WakerNotify {
+    fn notify(&self, _id: usize) {
+        self.0.wake_by_ref();
+    }
+}
+
+#[cfg(all(feature = "std-future", not(feature = "rpc")))]
+thread_local! {
+    // The waker of the task that is polling a promise through `std::future::Future`, if any.
+    static CURRENT_WAKER: RefCell<Option<::std::task::Waker>> = RefCell::new(None);
+}
+
+#[cfg(feature = "std-future")]
+impl <T, E> ::std::future::Future for Promise<T, E> {
+    type Output = Result<T, E>;
+
+    #[cfg(feature = "rpc")]
+    fn poll(self: ::std::pin::Pin<&mut Self>, cx: &mut ::std::task::Context)
+            -> ::std::task::Poll<Result<T, E>>
+    {
+        let notify = ::futures::executor::NotifyHandle::from(
+            ::std::sync::Arc::new(WakerNotify(cx.waker().clone())));
+        match ::futures::executor::spawn(self.get_mut()).poll_future_notify(&notify, 0) {
+            Ok(::futures::Async::Ready(v)) => ::std::task::Poll::Ready(Ok(v)),
+            Ok(::futures::Async::NotReady) => ::std::task::Poll::Pending,
+            Err(e) => ::std::task::Poll::Ready(Err(e)),
+        }
+    }
+
+    #[cfg(not(feature = "rpc"))]
+    fn poll(self: ::std::pin::Pin<&mut Self>, cx: &mut ::std::task::Context)
+            -> ::std::task::Poll<Result<T, E>>
+    {
+        let previous = CURRENT_WAKER.with(|w| w.replace(Some(cx.waker().clone())));
+        let result = self.get_mut().poll_now();
+        CURRENT_WAKER.with(|w| *w.borrow_mut() = previous);
+        match result {
+            Some(result) => ::std::task::Poll::Ready(result),
+            None => ::std::task::Poll::Pending,
+        }
+    }
+}
+
+#[cfg(not(feature = "rpc"))]
+thread_local! {
+    // Counts the promises fulfilled on this thread, so that an `Executor` can tell whether
+    // polling again could get anywhere.
+    static FULFILLED: Cell<u64> = Cell::new(0);
+}
+
+#[cfg(not(feature = "rpc"))]
+struct FulfillerSlot<T, E> {
+    result: Option<Result<T, E>>,
+    #[cfg(feature = "std-future")]
+    waker: Option<::std::task::Waker>,
+}
+
+/// Resolves the promise that was created along with it by `new_promise_and_fulfiller()`.
+/// If it is dropped without being used, the promise never resolves.
+pub struct Fulfiller<T, E> {
+    #[cfg(feature = "rpc")]
+    sender: ::futures::sync::oneshot::Sender<Result<T, E>>,
+
+    #[cfg(not(feature = "rpc"))]
+    slot: Rc<RefCell<FulfillerSlot<T, E>>>,
+}
+
+impl <T, E> Fulfiller<T, E> {
+    pub fn fulfill(self, value: T) {
+        self.resolve(Ok(value))
+    }
+
+    pub fn reject(self, error: E) {
+        self.resolve(Err(error))
+    }
+
+    /// Resolves the promise with `result`, whether it is a value or an error.
+    #[cfg(feature = "rpc")]
+    pub fn resolve(self, result: Result<T, E>) {
+        // The promise may have been dropped already.
+        let _ = self.sender.send(result);
+    }
+
+    /// Resolves the promise with `result`, whether it is a value or an error.
+    #[cfg(not(feature = "rpc"))]
+    pub fn resolve(self, result: Result<T, E>) {
+        FULFILLED.with(|count| count.set(count.get() + 1));
+        let mut slot = self.slot.borrow_mut();
+        slot.result = Some(result);
+        #[cfg(feature = "std-future")]
+        {
+            if let Some(waker) = slot.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Returns a promise that resolves once the returned `Fulfiller` says so. Useful for
+/// bridging callbacks into promises, and for controlling when things happen in tests.
+#[cfg(feature = "rpc")]
+pub fn new_promise_and_fulfiller<T, E>() -> (Promise<T, E>, Fulfiller<T, E>)
+    where T: 'static, E: 'static
+{
+    let (sender, receiver) = ::futures::sync::oneshot::channel();
+    let promise = receiver.then(|result| -> Box<Future<Item=T, Error=E>> {
+        match result {
+            Ok(result) => Box::new(::futures::future::result(result)),
+            Err(_canceled) => Box::new(::futures::future::empty()),
+        }
+    });
+    (Promise::from_future(promise), Fulfiller { sender: sender })
+}
+
+/// Returns a promise that resolves once the returned `Fulfiller` says so. Useful for
+/// bridging callbacks into promises, and for controlling when things happen in tests.
+#[cfg(not(feature = "rpc"))]
+pub fn new_promise_and_fulfiller<T, E>() -> (Promise<T, E>, Fulfiller<T, E>)
+    where T: 'static, E: 'static
+{
+    let slot = Rc::new(RefCell::new(FulfillerSlot {
+        result: None,
+        #[cfg(feature = "std-future")]
+        waker: None,
+    }));
+    let promise_slot = slot.clone();
+    let promise = Promise::from_poll_fn(move || {
+        let mut slot = promise_slot.borrow_mut();
+        let result = slot.result.take();
+        #[cfg(feature = "std-future")]
+        {
+            if result.is_none() {
+                slot.waker = CURRENT_WAKER.with(|w| w.borrow().clone());
+            }
+        }
+        result
+    });
+    (promise, Fulfiller { slot: slot })
+}
+
+/// Drives promises that fail with `E` to completion on the current thread. Meant for tests and
+/// simple programs that have no event loop of their own.
+pub struct Executor<E> {
+    #[cfg(feature = "rpc")]
+    tasks: Vec<::futures::executor::Spawn<Promise<(), E>>>,
+    #[cfg(feature = "rpc")]
+    notify: ::std::sync::Arc<ThreadNotify>,
+
+    #[cfg(not(feature = "rpc"))]
+    tasks: Vec<Promise<(), E>>,
+}
+
+#[cfg(feature = "rpc")]
+struct ThreadNotify {
+    thread: ::std::thread::Thread,
+    woken: ::std::sync::atomic::AtomicBool,
+}
+
+#[cfg(feature = "rpc")]
+impl ::futures::executor::Notify for ThreadNotify {
+    fn notify(&self, _id: usize) {
+        self.woken.store(true, ::std::sync::atomic::Ordering::SeqCst);
+        self.thread.unpark();
+    }
+}
+
+impl <E> Executor<E> where E: 'static {
+    #[cfg(feature = "rpc")]
+    pub fn new() -> Executor<E> {
+        Executor {
+            tasks: Vec::new(),
+            notify: ::std::sync::Arc::new(ThreadNotify {
+                thread: ::std::thread::current(),
+                woken: ::std::sync::atomic::AtomicBool::new(false),
+            }),
+        }
+    }
+
+    #[cfg(not(feature = "rpc"))]
+    pub fn new() -> Executor<E> {
+        Executor { tasks: Vec::new() }
+    }
+
+    /// Adds `task` to the promises that are run alongside the ones passed to `block_on()`.
+    /// If `task` fails, the `run_until_stalled()` or `block_on()` that ran it returns the error.
+    pub fn spawn(&mut self, task: Promise<(), E>) {
+        #[cfg(feature = "rpc")]
+        self.tasks.push(::futures::executor::spawn(task));
+        #[cfg(not(feature = "rpc"))]
+        self.tasks.push(task);
+    }
+
+    /// The number of spawned tasks that have not finished yet.
+    pub fn task_count(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Polls every spawned task once, dropping the ones that have finished. Returns whether
+    /// any of them finished, or the error of the first one that failed. The tasks after that
+    /// one are left for the next run.
+    fn run_tasks(&mut self) -> Result<bool, E> {
+        let before = self.tasks.len();
+        let mut remaining = Vec::with_capacity(before);
+        let mut tasks = ::std::mem::replace(&mut self.tasks, Vec::new()).into_iter();
+        let mut failure = None;
+        for mut task in &mut tasks {
+            #[cfg(feature = "rpc")]
+            let result = match task.poll_future_notify(&self.notify, 0) {
+                Ok(::futures::Async::Ready(())) => Some(Ok(())),
+                Ok(::futures::Async::NotReady) => None,
+                Err(e) => Some(Err(e)),
+            };
+            #[cfg(not(feature = "rpc"))]
+            let result = task.poll_now();
+            match result {
+                None => remaining.push(task),
+                Some(Ok(())) => (),
+                Some(Err(e)) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+        remaining.extend(tasks);
+        // Tasks spawned while the others ran.
+        remaining.extend(self.tasks.drain(..));
+        self.tasks = remaining;
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(self.tasks.len() < before),
+        }
+    }
+
+    /// Runs the spawned tasks until none of them can make progress without outside help, or
+    /// until one of them fails.
+    #[cfg(feature = "rpc")]
+    pub fn run_until_stalled(&mut self) -> Result<(), E> {
+        loop {
+            self.notify.woken.store(false, ::std::sync::atomic::Ordering::SeqCst);
+            let finished = try!(self.run_tasks());
+            if !finished && !self.notify.woken.load(::std::sync::atomic::Ordering::SeqCst) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Runs the spawned tasks until none of them can make progress without outside help, or
+    /// until one of them fails.
+    #[cfg(not(feature = "rpc"))]
+    pub fn run_until_stalled(&mut self) -> Result<(), E> {
+        loop {
+            let fulfilled = FULFILLED.with(|count| count.get());
+            let finished = try!(self.run_tasks());
+            if !finished && fulfilled == FULFILLED.with(|count| count.get()) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Runs `promise`, and the spawned tasks, until `promise` resolves or one of the tasks
+    /// fails. Blocks the thread while nothing can make progress, so that another thread can
+    /// wake it.
+    #[cfg(feature = "rpc")]
+    pub fn block_on<T>(&mut self, promise: Promise<T, E>) -> Result<T, E> {
+        use std::sync::atomic::Ordering;
+
+        let mut promise = ::futures::executor::spawn(promise);
+        loop {
+            self.notify.woken.store(false, Ordering::SeqCst);
+            match promise.poll_future_notify(&self.notify, 0) {
+                Ok(::futures::Async::Ready(v)) => return Ok(v),
+                Ok(::futures::Async::NotReady) => (),
+                Err(e) => return Err(e),
+            }
+            let finished = try!(self.run_tasks());
+            while !finished && !self.notify.woken.load(Ordering::SeqCst) {
+                ::std::thread::park();
+            }
+        }
+    }
+
+    /// Runs `promise`, and the spawned tasks, until `promise` resolves or one of the tasks
+    /// fails. Panics if neither can happen any more, because everything is waiting on a
+    /// `Fulfiller` that nothing will use.
+    #[cfg(not(feature = "rpc"))]
+    pub fn block_on<T>(&mut self, mut promise: Promise<T, E>) -> Result<T, E> {
+        loop {
+            let fulfilled = FULFILLED.with(|count| count.get());
+            if let Some(result) = promise.poll_now() {
+                return result;
+            }
+            let finished = try!(self.run_tasks());
+            if !finished && fulfilled == FULFILLED.with(|count| count.get()) {
+                panic!("block_on(): the promise can never resolve");
+            }
+        }
+    }
+}
+
+impl <E> Default for Executor<E> where E: 'static {
+    fn default() -> Executor<E> {
+        Executor::new()
+    }
+}
+
 /// A promise for a result from a method call.
 #[must_use]
 pub struct RemotePromise<Results> where Results: Pipelined + for<'a> Owned<'a> + 'static {
@@ -237,3 +674,136 @@ pub trait Server {
                      -> Promise<(), Error>;
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::{new_promise_and_fulfiller, Executor, Promise};
+
+    /// Maps the value of `promise` through `futures::Future` under `rpc`, and through the
+    /// inherent method otherwise.
+    #[cfg(feature = "rpc")]
+    fn map<T, U, E, F>(promise: Promise<T, E>, f: F) -> Promise<U, E>
+        where F: FnOnce(T) -> U + 'static, T: 'static, U: 'static, E: 'static
+    {
+        Promise::from_future(::futures::Future::map(promise, f))
+    }
+
+    #[cfg(not(feature = "rpc"))]
+    fn map<T, U, E, F>(promise: Promise<T, E>, f: F) -> Promise<U, E>
+        where F: FnOnce(T) -> U + 'static, T: 'static, U: 'static, E: 'static
+    {
+        promise.map(f)
+    }
+
+    #[cfg(not(feature = "rpc"))]
+    #[test]
+    fn promise_combinators() {
+        let mut executor = Executor::new();
+        let doubled = Promise::<u32, String>::ok(21).map(|v| v * 2);
+        assert_eq!(executor.block_on(doubled), Ok(42));
+
+        let failed = Promise::<u32, u32>::err(1).map_err(|e| format!("error {}", e));
+        assert_eq!(executor.block_on(failed), Err("error 1".to_string()));
+
+        let recovered = Promise::<u32, String>::err("oops".to_string()).then(|result| {
+            match result {
+                Ok(v) => Promise::ok(v),
+                Err(_) => Promise::<u32, ()>::ok(0),
+            }
+        });
+        assert_eq!(Executor::new().block_on(recovered), Ok(0));
+
+        let checked = Promise::<u32, u32>::ok(7).then(|result| {
+            result.and_then(|v| if v > 5 { Err(v) } else { Ok(v) })
+        });
+        assert_eq!(Executor::new().block_on(checked), Err(7));
+    }
+
+    #[test]
+    fn fulfiller_and_join_all() {
+        let mut executor = Executor::new();
+        let (first, first_fulfiller) = new_promise_and_fulfiller::<u32, String>();
+        let (second, second_fulfiller) = new_promise_and_fulfiller::<u32, String>();
+        let (done, done_fulfiller) = new_promise_and_fulfiller::<(), String>();
+
+        // The task fulfills the promises that `block_on()` waits for, out of order.
+        executor.spawn(map(done, move |()| {
+            second_fulfiller.fulfill(2);
+            first_fulfiller.fulfill(1);
+        }));
+        executor.run_until_stalled().unwrap();
+        assert_eq!(executor.task_count(), 1);
+
+        done_fulfiller.fulfill(());
+        let all = Promise::join_all(vec![first, second, Promise::ok(3)]);
+        assert_eq!(executor.block_on(map(all, |values| values.iter().sum::<u32>())), Ok(6));
+        assert_eq!(executor.task_count(), 0);
+
+        let (failing, failing_fulfiller) = new_promise_and_fulfiller::<u32, String>();
+        let (never, never_fulfiller) = new_promise_and_fulfiller::<u32, String>();
+        failing_fulfiller.reject("no".to_string());
+        assert_eq!(executor.block_on(Promise::join_all(vec![failing, never])),
+                   Err("no".to_string()));
+        drop(never_fulfiller);
+    }
+
+    #[test]
+    fn failed_task() {
+        let mut executor = Executor::new();
+        let (failing, failing_fulfiller) = new_promise_and_fulfiller::<(), u32>();
+        let (pending, pending_fulfiller) = new_promise_and_fulfiller::<(), u32>();
+        executor.spawn(Promise::err(1));
+        executor.spawn(failing);
+        executor.spawn(pending);
+        assert_eq!(executor.run_until_stalled(), Err(1));
+        assert_eq!(executor.task_count(), 2);
+
+        // A task that fails while `block_on()` waits ends the wait.
+        let (never, _never_fulfiller) = new_promise_and_fulfiller::<(), u32>();
+        failing_fulfiller.reject(2);
+        assert_eq!(executor.block_on(never), Err(2));
+        assert_eq!(executor.task_count(), 1);
+
+        pending_fulfiller.fulfill(());
+        assert_eq!(executor.run_until_stalled(), Ok(()));
+        assert_eq!(executor.task_count(), 0);
+    }
+
+    #[cfg(not(feature = "rpc"))]
+    #[test]
+    #[should_panic(expected = "can never resolve")]
+    fn block_on_stalled_promise() {
+        let (promise, _fulfiller) = new_promise_and_fulfiller::<(), ()>();
+        let _ = Executor::new().block_on(promise);
+    }
+
+    #[cfg(feature = "std-future")]
+    #[test]
+    fn std_future() {
+        use std::future::Future;
+        use std::pin::Pin;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::task::{Context, Poll, Wake, Waker};
+
+        struct Flag(AtomicBool);
+
+        impl Wake for Flag {
+            fn wake(self: Arc<Self>) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let flag = Arc::new(Flag(AtomicBool::new(false)));
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let (promise, fulfiller) = new_promise_and_fulfiller::<u32, ()>();
+        let mut promise = map(promise, |v| v + 1);
+        assert_eq!(Pin::new(&mut promise).poll(&mut cx), Poll::Pending);
+        assert!(!flag.0.load(Ordering::SeqCst));
+        fulfiller.fulfill(1);
+        assert!(flag.0.load(Ordering::SeqCst));
+        assert_eq!(Pin::new(&mut promise).poll(&mut cx), Poll::Ready(Ok(2)));
+    }
+}
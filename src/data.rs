@@ -21,7 +21,7 @@
 
 //! Sequence of bytes.
 
-use private::layout::{PointerBuilder, PointerReader};
+use private::layout::{GrowableBlob, PointerBuilder, PointerReader};
 use Result;
 
 #[derive(Copy, Clone)]
@@ -59,6 +59,82 @@ impl <'a> ::traits::FromPointerBuilder<'a> for Builder<'a> {
     }
 }
 
+/// A data field that grows as bytes are appended to it, for filling in a field whose final
+/// length is not known up front. The field grows in place if it was the last thing allocated
+/// in its segment, and is moved to a new location otherwise. Room is reserved geometrically,
+/// so the field is trimmed to its contents by `into_builder()`, or when the builder is dropped.
+pub struct GrowableBuilder<'a> {
+    blob: GrowableBlob<'a>,
+}
+
+impl <'a> GrowableBuilder<'a> {
+    pub fn len(&self) -> u32 { self.blob.len() as u32 }
+
+    pub fn is_empty(&self) -> bool { self.blob.is_empty() }
+
+    pub fn push(&mut self, byte: u8) -> Result<()> {
+        self.blob.extend_from_slice(&[byte])
+    }
+
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) -> Result<()> {
+        self.blob.extend_from_slice(bytes)
+    }
+
+    /// Shrinks the field to `len` bytes. Panics if `len` is greater than the current length.
+    pub fn truncate(&mut self, len: u32) {
+        self.blob.truncate(len as usize);
+    }
+
+    pub fn into_builder(mut self) -> Result<Builder<'a>> {
+        self.blob.finish()
+    }
+}
+
+impl <'a> Drop for GrowableBuilder<'a> {
+    fn drop(&mut self) {
+        // This only fails if the field was replaced behind our back, leaving nothing to trim.
+        let _ = self.blob.finish();
+    }
+}
+
+impl <'a> ::std::ops::Deref for GrowableBuilder<'a> {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        self.blob.as_slice()
+    }
+}
+
+impl <'a> ::std::ops::DerefMut for GrowableBuilder<'a> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.blob.as_mut_slice()
+    }
+}
+
+impl <'a> ::std::io::Write for GrowableBuilder<'a> {
+    fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize> {
+        match self.extend_from_slice(buf) {
+            Ok(()) => Ok(buf.len()),
+            Err(e) => Err(::std::io::Error::other(e)),
+        }
+    }
+
+    fn flush(&mut self) -> ::std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl <'a> ::traits::FromPointerBuilder<'a> for GrowableBuilder<'a> {
+    fn init_pointer(builder: PointerBuilder<'a>, size: u32) -> GrowableBuilder<'a> {
+        builder.init_data(size);
+        let blob = GrowableBlob::new(builder, size, 0).expect("failed to take over new data");
+        GrowableBuilder { blob: blob }
+    }
+    fn get_from_pointer(builder: PointerBuilder<'a>) -> Result<GrowableBuilder<'a>> {
+        let len = try!(builder.get_data(::std::ptr::null(), 0)).len() as u32;
+        Ok(GrowableBuilder { blob: try!(GrowableBlob::new(builder, len, 0)) })
+    }
+}
+
 impl <'a> ::traits::SetPointerBuilder<Builder<'a>> for Reader<'a> {
     fn set_pointer_builder<'b>(pointer: PointerBuilder<'b>, value: Reader<'a>) -> Result<()> {
         pointer.set_data(value);
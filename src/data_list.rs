@@ -81,6 +81,18 @@ impl <'a> Builder<'a> {
 
     pub fn len(&self) -> u32 { self.builder.len() }
 
+    /// Drops the blobs from index `len` on, zeroing their bytes.
+    /// Errors if `len` exceeds the length.
+    pub fn truncate(&mut self, len: u32) -> Result<()> {
+        self.builder.truncate(len)
+    }
+
+    /// Sets the number of blobs to `len`; added ones are null. Growing may relocate the list,
+    /// so fetch fresh data builders afterwards.
+    pub fn resize(&mut self, len: u32) -> Result<()> {
+        self.builder.resize(len)
+    }
+
     pub fn as_reader(self) -> Reader<'a> {
         Reader { reader: self.builder.as_reader() }
     }
@@ -95,6 +107,11 @@ impl <'a> Builder<'a> {
     }
 }
 
+impl <'a, 'b> ::traits::SetListElement<::data::Reader<'b>> for Builder<'a> {
+    fn set_element(&mut self, index: u32, value: ::data::Reader<'b>) {
+        self.set(index, value)
+    }
+}
 
 impl <'a> FromPointerBuilder<'a> for Builder<'a> {
     fn init_pointer(builder: PointerBuilder<'a>, size : u32) -> Builder<'a> {
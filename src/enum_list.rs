@@ -90,6 +90,17 @@ impl <'a, T : ToU16 + FromU16> Builder<'a, T> {
 
     pub fn len(&self) -> u32 { self.builder.len() }
 
+    /// Drops every enumerant from index `len` on. Errors if `len` exceeds the length.
+    pub fn truncate(&mut self, len: u32) -> Result<()> {
+        self.builder.truncate(len)
+    }
+
+    /// Sets the length to `len`; added elements hold the enumerant numbered 0.
+    /// See `primitive_list::Builder::resize()`.
+    pub fn resize(&mut self, len: u32) -> Result<()> {
+        self.builder.resize(len)
+    }
+
     pub fn as_reader(self) -> Reader<'a, T> {
         Reader { reader: self.builder.as_reader(), marker: PhantomData, }
     }
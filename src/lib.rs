@@ -66,15 +66,31 @@ macro_rules! capnp_word {
   )
 }
 
+pub mod any_list;
 pub mod any_pointer;
+pub mod any_struct;
+pub mod broken;
 pub mod capability;
 pub mod data;
 pub mod data_list;
 pub mod enum_list;
+#[cfg(feature = "rpc")]
+pub mod limiter;
 pub mod list_list;
+#[cfg(feature = "rpc")]
+pub mod local;
+#[cfg(feature = "rpc")]
+pub mod membrane;
 pub mod message;
+#[cfg(feature = "rpc")]
+pub mod observer;
 pub mod primitive_list;
 pub mod private;
+#[cfg(feature = "rpc")]
+pub mod queued;
+#[cfg(feature = "rpc")]
+pub mod revocable;
+pub mod schema_compat;
 pub mod serialize;
 pub mod serialize_packed;
 pub mod struct_list;
@@ -188,8 +204,64 @@ pub struct Error {
     /// should read only this field in making its decision.
     pub kind: ErrorKind,
 
+    /// What specifically went wrong, for code that needs to tell decoding failures apart,
+    /// e.g. to log them differently or to count them.
+    pub reason: ErrorReason,
+
     /// Human-readable failure description.
     pub description: String,
+
+    /// Where in the message the error was found, for errors raised while decoding one.
+    pub location: Option<ErrorLocation>,
+
+    source: Option<::std::sync::Arc<::std::error::Error + Send + Sync>>,
+}
+
+/// Where in a message an error was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorLocation {
+    /// The segment holding the offending pointer.
+    pub segment_id: u32,
+
+    /// The offset of the offending pointer from the start of its segment, in words.
+    pub word_offset: u32,
+
+    /// The steps that led from the start of the traversal to the offending pointer, outermost
+    /// first. Empty if the pointer was read directly rather than found by a traversal.
+    pub path: Vec<PathStep>,
+}
+
+/// One step on the way from the start of a traversal to the pointer being visited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathStep {
+    /// The pointer that the traversal started from.
+    Root,
+
+    /// The pointer field with the given index in a struct's pointer section.
+    Pointer(u32),
+
+    /// The element with the given index in a list.
+    ListElement(u32),
+}
+
+impl ::std::fmt::Display for ErrorLocation {
+    fn fmt(&self, fmt: &mut ::std::fmt::Formatter) -> ::std::result::Result<(), ::std::fmt::Error> {
+        try!(write!(fmt, "segment {}, word {}", self.segment_id, self.word_offset));
+        for (idx, step) in self.path.iter().enumerate() {
+            try!(write!(fmt, "{}{}", if idx == 0 { "; path: " } else { " \u{2192} " }, step));
+        }
+        Ok(())
+    }
+}
+
+impl ::std::fmt::Display for PathStep {
+    fn fmt(&self, fmt: &mut ::std::fmt::Formatter) -> ::std::result::Result<(), ::std::fmt::Error> {
+        match *self {
+            PathStep::Root => write!(fmt, "root"),
+            PathStep::Pointer(index) => write!(fmt, "pointer {}", index),
+            PathStep::ListElement(index) => write!(fmt, "list element {}", index),
+        }
+    }
 }
 
 /// The general nature of an error. The purpose of this enum is not to describe the error itself,
@@ -216,18 +288,109 @@ pub enum ErrorKind {
     Unimplemented,
 }
 
+/// The specific cause of an error. Unlike `ErrorKind`, which says how a caller might respond,
+/// this says what actually went wrong, mostly while decoding a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorReason {
+    /// No more specific reason is known.
+    Other,
+
+    /// A pointer's target lies outside of its segment.
+    OutOfBoundsPointer,
+
+    /// The traversal limit set in `ReaderOptions` was exceeded.
+    ReadLimitExceeded,
+
+    /// The nesting limit set in `ReaderOptions` was exceeded.
+    NestingLimitExceeded,
+
+    /// A far pointer refers to a segment that is not part of the message.
+    InvalidSegmentId,
+
+    /// The segment table at the start of a serialized message is malformed.
+    MalformedSegmentTable,
+
+    /// A far pointer or its landing pad is malformed.
+    MalformedFarPointer,
+
+    /// A list's element tag or size is malformed.
+    MalformedList,
+
+    /// An index or length lies beyond the end of a list.
+    IndexOutOfRange,
+
+    /// A pointer has the wrong kind or element size for how it is being read,
+    /// e.g. a list where a struct was expected.
+    WrongPointerKind,
+
+    /// A pointer is of the reserved "other" kind but is not a capability.
+    UnknownPointerType,
+
+    /// A capability pointer refers to a capability that does not exist.
+    InvalidCapability,
+
+    /// A text blob does not end in a NUL byte.
+    MissingNulTerminator,
+
+    /// A text blob is not valid UTF-8.
+    BadUtf8,
+
+    /// An enum value or union discriminant was not present in the schema.
+    NotInSchema,
+
+    /// An I/O operation failed.
+    Io,
+}
+
 impl Error {
     pub fn failed(description: String) -> Error {
-        Error { description: description, kind: ErrorKind::Failed }
+        Error::new(ErrorKind::Failed, ErrorReason::Other, description)
     }
     pub fn overloaded(description: String) -> Error {
-        Error { description: description, kind: ErrorKind::Overloaded }
+        Error::new(ErrorKind::Overloaded, ErrorReason::Other, description)
     }
     pub fn disconnected(description: String) -> Error {
-        Error { description: description, kind: ErrorKind::Disconnected }
+        Error::new(ErrorKind::Disconnected, ErrorReason::Other, description)
     }
     pub fn unimplemented(description: String) -> Error {
-        Error { description: description, kind: ErrorKind::Unimplemented }
+        Error::new(ErrorKind::Unimplemented, ErrorReason::Other, description)
+    }
+
+    /// Constructs a `Failed` error with the given reason.
+    pub fn with_reason(reason: ErrorReason, description: String) -> Error {
+        Error::new(ErrorKind::Failed, reason, description)
+    }
+
+    fn new(kind: ErrorKind, reason: ErrorReason, description: String) -> Error {
+        Error { kind: kind, reason: reason, description: description, location: None, source: None }
+    }
+
+    /// Attaches the lower-level error that caused this one. It is returned by `source()`.
+    pub fn with_source<E>(mut self, source: E) -> Error
+        where E: ::std::error::Error + Send + Sync + 'static
+    {
+        self.source = Some(::std::sync::Arc::new(source));
+        self
+    }
+
+    pub fn has_reason(&self, reason: ErrorReason) -> bool {
+        self.reason == reason
+    }
+
+    pub fn is_out_of_bounds(&self) -> bool {
+        self.reason == ErrorReason::OutOfBoundsPointer
+    }
+
+    pub fn is_read_limit_exceeded(&self) -> bool {
+        self.reason == ErrorReason::ReadLimitExceeded
+    }
+
+    pub fn is_nesting_limit_exceeded(&self) -> bool {
+        self.reason == ErrorReason::NestingLimitExceeded
+    }
+
+    pub fn is_io(&self) -> bool {
+        self.reason == ErrorReason::Io
     }
 }
 
@@ -243,19 +406,19 @@ impl ::std::convert::From<::std::io::Error> for Error {
             io::ErrorKind::NotConnected  => ErrorKind::Disconnected,
             _ => ErrorKind::Failed,
         };
-        Error { description: format!("{}", err), kind: kind }
+        Error::new(kind, ErrorReason::Io, format!("{}", err)).with_source(err)
     }
 }
 
 impl ::std::convert::From<::std::string::FromUtf8Error> for Error {
     fn from(err: ::std::string::FromUtf8Error) -> Error {
-        Error::failed(format!("{}", err))
+        Error::with_reason(ErrorReason::BadUtf8, format!("{}", err)).with_source(err)
     }
 }
 
 impl ::std::convert::From<::std::str::Utf8Error> for Error {
     fn from(err: ::std::str::Utf8Error) -> Error {
-        Error::failed(format!("{}", err))
+        Error::with_reason(ErrorReason::BadUtf8, format!("{}", err)).with_source(err)
     }
 }
 
@@ -269,13 +432,19 @@ impl ::std::convert::From<futures::sync::oneshot::Canceled> for Error {
 
 impl ::std::convert::From<NotInSchema> for Error {
     fn from(e: NotInSchema) -> Error {
-        Error::failed(format!("Enum value or union discriminant {} was not present in schema.", e.0))
+        Error::with_reason(
+            ErrorReason::NotInSchema,
+            format!("Enum value or union discriminant {} was not present in schema.", e.0)).with_source(e)
     }
 }
 
 impl ::std::fmt::Display for Error {
     fn fmt(&self, fmt: &mut ::std::fmt::Formatter) -> ::std::result::Result<(), ::std::fmt::Error> {
-        write!(fmt, "{:?}: {}", self.kind, self.description)
+        try!(write!(fmt, "{:?}: {}", self.kind, self.description));
+        match self.location {
+            Some(ref location) => write!(fmt, " ({})", location),
+            None => Ok(()),
+        }
     }
 }
 
@@ -283,8 +452,11 @@ impl ::std::error::Error for Error {
     fn description(&self) -> &str {
         &self.description
     }
-    fn cause(&self) -> Option<&::std::error::Error> {
-        None
+    fn source(&self) -> Option<&(::std::error::Error + 'static)> {
+        match self.source {
+            Some(ref source) => Some(&**source),
+            None => None,
+        }
     }
 }
 
@@ -0,0 +1,264 @@
+// Copyright (c) 2013-2015 Sandstorm Development Group, Inc. and contributors
+// Licensed under the MIT License:
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Wrappers that limit how many calls a capability has in flight at once.
+
+use futures::Future;
+use futures::sync::oneshot;
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use any_pointer;
+use capability::Promise;
+use private::capability::{ClientHook, ParamsHook, ResultsHook};
+use {Error, MessageSize, Result};
+
+/// What to do with a call that arrives when the limit has been reached.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WhenFull {
+    /// Hold the call back until another call finishes. Calls are started in the order in
+    /// which they were made.
+    Queue,
+
+    /// Fail the call right away with an error of kind `ErrorKind::Overloaded`.
+    Fail,
+}
+
+struct QueuedCall {
+    interface_id: u64,
+    method_id: u16,
+    params: Box<ParamsHook>,
+    results: Box<ResultsHook>,
+    started: oneshot::Sender<Promise<(), Error>>,
+}
+
+struct Inner {
+    target: Box<ClientHook>,
+    max_in_flight: usize,
+    when_full: WhenFull,
+    in_flight: Cell<usize>,
+    queue: RefCell<VecDeque<QueuedCall>>,
+}
+
+/// Holds one of the limited number of places for calls in flight, and gives it to the next
+/// queued call when dropped.
+struct Slot {
+    inner: Rc<Inner>,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.inner.in_flight.set(self.inner.in_flight.get() - 1);
+        while self.inner.in_flight.get() < self.inner.max_in_flight {
+            let next = self.inner.queue.borrow_mut().pop_front();
+            match next {
+                None => break,
+                // The caller has given up on this call.
+                Some(ref call) if call.started.is_canceled() => continue,
+                Some(call) => {
+                    let QueuedCall { interface_id, method_id, params, results, started } = call;
+                    let promise = start(&self.inner, interface_id, method_id, params, results);
+                    let _ = started.send(promise);
+                }
+            }
+        }
+    }
+}
+
+fn start(inner: &Rc<Inner>, interface_id: u64, method_id: u16,
+         params: Box<ParamsHook>, results: Box<ResultsHook>) -> Promise<(), Error>
+{
+    inner.in_flight.set(inner.in_flight.get() + 1);
+    let slot = Slot { inner: inner.clone() };
+    let call = inner.target.call(interface_id, method_id, params, results);
+    Promise::from_future(call.then(move |result| {
+        drop(slot);
+        result
+    }))
+}
+
+/// A capability that passes calls on to `target` while no more than `max_in_flight` of them
+/// have yet to finish. A call counts as finished when its promise completes or is dropped.
+pub struct Client {
+    inner: Rc<Inner>,
+}
+
+impl Client {
+    /// Fails if `max_in_flight` is zero, as no call could ever start.
+    pub fn new(target: Box<ClientHook>, max_in_flight: usize, when_full: WhenFull)
+               -> Result<Client>
+    {
+        if max_in_flight == 0 {
+            return Err(Error::failed(
+                "A limiter must allow at least one call in flight.".to_string()));
+        }
+        Ok(Client {
+            inner: Rc::new(Inner {
+                target: target,
+                max_in_flight: max_in_flight,
+                when_full: when_full,
+                in_flight: Cell::new(0),
+                queue: RefCell::new(VecDeque::new()),
+            }),
+        })
+    }
+
+    /// The number of calls that have been started but have not finished yet.
+    pub fn in_flight(&self) -> usize {
+        self.inner.in_flight.get()
+    }
+
+    /// The number of calls waiting for a place to free up.
+    pub fn queued(&self) -> usize {
+        self.inner.queue.borrow().len()
+    }
+}
+
+impl ClientHook for Client {
+    fn add_ref(&self) -> Box<ClientHook> {
+        Box::new(Client { inner: self.inner.clone() })
+    }
+
+    fn new_call(&self, interface_id: u64, method_id: u16, size_hint: Option<MessageSize>)
+                -> ::capability::Request<any_pointer::Owned, any_pointer::Owned>
+    {
+        ::local::new_forwarding_call(self.add_ref(), interface_id, method_id, size_hint)
+    }
+
+    fn call(&self, interface_id: u64, method_id: u16,
+            params: Box<ParamsHook>, results: Box<ResultsHook>) -> Promise<(), Error>
+    {
+        if self.inner.in_flight.get() < self.inner.max_in_flight {
+            return start(&self.inner, interface_id, method_id, params, results);
+        }
+
+        match self.inner.when_full {
+            WhenFull::Fail => {
+                Promise::err(Error::overloaded(
+                    format!("Too many calls in flight. Limit: {}", self.inner.max_in_flight)))
+            }
+            WhenFull::Queue => {
+                let (sender, receiver) = oneshot::channel();
+                self.inner.queue.borrow_mut().push_back(QueuedCall {
+                    interface_id: interface_id,
+                    method_id: method_id,
+                    params: params,
+                    results: results,
+                    started: sender,
+                });
+                Promise::from_future(receiver.map_err(Error::from).and_then(|promise| promise))
+            }
+        }
+    }
+
+    fn get_brand(&self) -> usize {
+        0
+    }
+
+    fn get_ptr(&self) -> usize {
+        &*self.inner as *const _ as usize
+    }
+
+    // Handing out the target, even once it has resolved, would let callers get around the limit.
+    fn get_resolved(&self) -> Option<Box<ClientHook>> {
+        None
+    }
+
+    fn when_more_resolved(&self) -> Option<Promise<Box<ClientHook>, Error>> {
+        None
+    }
+
+    fn is_null(&self) -> bool {
+        self.inner.target.is_null()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::Future;
+
+    use capability::Promise;
+    use private::capability::ClientHook;
+    use private::test_server::{self, finish_first, Pending};
+    use {Error, ErrorKind};
+    use super::WhenFull;
+
+    fn setup(when_full: WhenFull) -> (super::Client, Pending) {
+        let (server, pending) = test_server::new_client();
+        (super::Client::new(server.hook, 2, when_full).unwrap(), pending)
+    }
+
+    /// Makes a call that stays pending until the test finishes it.
+    fn call(client: &ClientHook) -> Promise<(), Error> {
+        Promise::from_future(client.new_call(0, 2, None).hook.send().promise.map(|_| ()))
+    }
+
+    #[test]
+    fn zero_limit() {
+        let (server, _pending) = test_server::new_client();
+        assert!(super::Client::new(server.hook, 0, WhenFull::Queue).is_err());
+    }
+
+    #[test]
+    fn fail_when_full() {
+        let (client, pending) = setup(WhenFull::Fail);
+        let first = call(&client);
+        let _second = call(&client);
+        assert_eq!(client.in_flight(), 2);
+        assert_eq!(call(&client).wait().unwrap_err().kind, ErrorKind::Overloaded);
+        assert_eq!(pending.borrow().len(), 2);
+
+        finish_first(&pending);
+        first.wait().unwrap();
+        assert_eq!(client.in_flight(), 1);
+        let _fourth = call(&client);
+        assert_eq!(pending.borrow().len(), 2);
+    }
+
+    #[test]
+    fn queue_when_full() {
+        let (client, pending) = setup(WhenFull::Queue);
+        let first = call(&client);
+        let second = call(&client);
+        let third = call(&client);
+        let dropped = call(&client);
+        let fifth = call(&client);
+        assert_eq!(pending.borrow().len(), 2);
+        assert_eq!(client.queued(), 3);
+        drop(dropped);
+
+        // Finishing a call starts the next one that is still wanted.
+        finish_first(&pending);
+        first.wait().unwrap();
+        assert_eq!((client.in_flight(), client.queued()), (2, 2));
+        drop(second);
+        assert_eq!((client.in_flight(), client.queued()), (2, 0));
+
+        pending.borrow_mut().remove(0);
+        finish_first(&pending);
+        finish_first(&pending);
+        third.wait().unwrap();
+        fifth.wait().unwrap();
+        assert_eq!(client.in_flight(), 0);
+    }
+}
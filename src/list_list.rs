@@ -92,6 +92,18 @@ impl <'a, T> Builder<'a, T> where T: for<'b> ::traits::Owned<'b> {
 
     pub fn len(&self) -> u32 { self.builder.len() }
 
+    /// Drops the inner lists from index `len` on, along with their contents.
+    /// Errors if `len` exceeds the length.
+    pub fn truncate(&mut self, len: u32) -> Result<()> {
+        self.builder.truncate(len)
+    }
+
+    /// Sets the number of inner lists to `len`; added ones are null. Growing may move the
+    /// outer list, so builders obtained from it beforehand must not be used.
+    pub fn resize(&mut self, len: u32) -> Result<()> {
+        self.builder.resize(len)
+    }
+
     pub fn as_reader(self) -> Reader<'a, T> {
         Reader { reader: self.builder.as_reader(), marker: ::std::marker::PhantomData }
     }
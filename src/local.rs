@@ -0,0 +1,458 @@
+// Copyright (c) 2013-2015 Sandstorm Development Group, Inc. and contributors
+// Licensed under the MIT License:
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! In-process implementations of the capability hooks, for calling a `capability::Server`
+//! that lives in the same process.
+//!
+//! Params and results are held in ordinary heap-allocated messages. Calls are dispatched to the
+//! server as soon as they are made, so they reach it in order. A call that the server makes on
+//! itself is dispatched once its promise is first polled instead.
+
+use futures::Future;
+use futures::future::Shared;
+use futures::sync::oneshot;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use any_pointer;
+use capability::{Promise, RemotePromise, Server};
+use message::{self, HeapAllocator};
+use private::capability::{ClientHook, ParamsHook, PipelineHook, PipelineOp, RequestHook,
+                          ResponseHook, ResultsHook, ServerHook};
+use {Error, MessageSize};
+
+/// The results of a completed call.
+pub struct Response {
+    message: Rc<message::Builder<HeapAllocator>>,
+}
+
+impl ResponseHook for Response {
+    fn get<'a>(&'a self) -> ::Result<any_pointer::Reader<'a>> {
+        self.message.get_root_as_reader()
+    }
+}
+
+struct Params {
+    message: message::Builder<HeapAllocator>,
+}
+
+impl ParamsHook for Params {
+    fn get<'a>(&'a self) -> ::Result<any_pointer::Reader<'a>> {
+        self.message.get_root_as_reader()
+    }
+}
+
+/// Results as seen by the server. Once the server drops them, they are handed to the caller.
+struct Results {
+    message: Option<message::Builder<HeapAllocator>>,
+    done: Option<oneshot::Sender<message::Builder<HeapAllocator>>>,
+}
+
+impl Drop for Results {
+    fn drop(&mut self) {
+        if let (Some(message), Some(done)) = (self.message.take(), self.done.take()) {
+            // The caller may have given up on the call already, in which case there is no one
+            // left to tell.
+            let _ = done.send(message);
+        }
+    }
+}
+
+impl ResultsHook for Results {
+    fn get<'a>(&'a mut self) -> ::Result<any_pointer::Builder<'a>> {
+        match self.message {
+            Some(ref mut message) => message.get_root(),
+            None => unreachable!(),
+        }
+    }
+
+    fn allow_cancellation(&self) {}
+
+    fn tail_call(self: Box<Self>, request: Box<RequestHook>) -> Promise<(), Error> {
+        self.direct_tail_call(request).0
+    }
+
+    fn direct_tail_call(mut self: Box<Self>, request: Box<RequestHook>)
+                        -> (Promise<(), Error>, Box<PipelineHook>)
+    {
+        let RemotePromise { promise, pipeline } = request.send();
+        let promise = promise.and_then(move |response| {
+            try!(self.get()).set_as(try!(response.hook.get()))
+        });
+        (Promise::from_future(promise), pipeline.hook)
+    }
+}
+
+/// A call to a `Client` whose params are still being filled in.
+pub struct Request {
+    message: message::Builder<HeapAllocator>,
+    interface_id: u64,
+    method_id: u16,
+    client: Box<ClientHook>,
+}
+
+impl Request {
+    pub fn new(interface_id: u64, method_id: u16, size_hint: Option<MessageSize>,
+               client: Box<ClientHook>) -> Request
+    {
+        Request {
+            message: ::private::capability::new_message(size_hint),
+            interface_id: interface_id,
+            method_id: method_id,
+            client: client,
+        }
+    }
+}
+
+/// Starts a call whose params, once sent, are delivered to `client.call()`. This is the
+/// `new_call()` of clients that handle each call themselves, e.g. by passing it on to another
+/// capability.
+pub fn new_forwarding_call(client: Box<ClientHook>, interface_id: u64, method_id: u16,
+                           size_hint: Option<MessageSize>)
+                           -> ::capability::Request<any_pointer::Owned, any_pointer::Owned>
+{
+    ::capability::Request::new(Box::new(Request::new(interface_id, method_id, size_hint, client)))
+}
+
+impl RequestHook for Request {
+    fn get<'a>(&'a mut self) -> any_pointer::Builder<'a> {
+        self.message.get_root().unwrap()
+    }
+
+    fn get_brand(&self) -> usize {
+        0
+    }
+
+    fn send<'a>(self: Box<Self>) -> RemotePromise<any_pointer::Owned> {
+        let tmp = *self;
+        let Request { message, interface_id, method_id, client } = tmp;
+        let (done, results_done) = oneshot::channel();
+        let params = Params { message: message };
+        let results = Results { message: Some(message::Builder::new_default()), done: Some(done) };
+
+        let message = client.call(interface_id, method_id, Box::new(params), Box::new(results))
+            .join(results_done.map_err(Error::from))
+            .map(|((), message)| Rc::new(message));
+        let message: Box<Future<Item=Rc<message::Builder<HeapAllocator>>, Error=Error>> =
+            Box::new(message);
+        let message = message.shared();
+
+        let promise = message.clone()
+            .map(|message| {
+                ::capability::Response::new(Box::new(Response { message: (*message).clone() }))
+            })
+            .map_err(|e| (*e).clone());
+
+        RemotePromise {
+            promise: Promise::from_future(promise),
+            pipeline: any_pointer::Pipeline::new(Box::new(Pipeline { message: message })),
+        }
+    }
+
+    fn tail_send(self: Box<Self>)
+                 -> Option<(u32, Promise<(), Error>, Box<PipelineHook>)>
+    {
+        None
+    }
+}
+
+type SharedMessage = Shared<Box<Future<Item=Rc<message::Builder<HeapAllocator>>, Error=Error>>>;
+
+/// Looks up the capability that `ops` lead to in the results of a completed call.
+fn get_pipelined_cap(message: &message::Builder<HeapAllocator>, ops: &[PipelineOp])
+                     -> Box<ClientHook>
+{
+    let cap = message.get_root_as_reader::<any_pointer::Reader>()
+        .and_then(|root| root.get_pipelined_cap(ops));
+    match cap {
+        Ok(cap) => cap,
+        Err(e) => ::broken::new_cap(e),
+    }
+}
+
+/// Gives access to the capabilities in the results of a local call, before or after the call
+/// has completed.
+struct Pipeline {
+    message: SharedMessage,
+}
+
+impl PipelineHook for Pipeline {
+    fn add_ref(&self) -> Box<PipelineHook> {
+        Box::new(Pipeline { message: self.message.clone() })
+    }
+
+    fn get_pipelined_cap(&self, ops: &[PipelineOp]) -> Box<ClientHook> {
+        match self.message.peek() {
+            Some(Ok(message)) => get_pipelined_cap(&message, ops),
+            Some(Err(e)) => ::broken::new_cap((*e).clone()),
+            None => {
+                let ops = ops.to_vec();
+                let cap = self.message.clone()
+                    .map(move |message| get_pipelined_cap(&message, &ops))
+                    .map_err(|e| (*e).clone());
+                Box::new(::queued::Client::new(Promise::from_future(cap)))
+            }
+        }
+    }
+}
+
+/// A capability that calls a `Server` in this process.
+///
+/// A call is handed to the server right away, from within `call()`, rather than when its promise
+/// is first polled. That way calls reach the server in the order in which they were made, which
+/// `queued::Client` relies on when it forwards its queue. The exception is a call that the server
+/// makes on itself while it is handling another one: the server is busy then, so that call is
+/// handed over once its promise is first polled, by which time the server has returned.
+pub struct Client {
+    server: Rc<RefCell<Box<Server>>>,
+}
+
+impl Client {
+    pub fn new(server: Box<Server>) -> Client {
+        Client { server: Rc::new(RefCell::new(server)) }
+    }
+}
+
+impl ClientHook for Client {
+    fn add_ref(&self) -> Box<ClientHook> {
+        Box::new(Client { server: self.server.clone() })
+    }
+
+    fn new_call(&self, interface_id: u64, method_id: u16, size_hint: Option<MessageSize>)
+                -> ::capability::Request<any_pointer::Owned, any_pointer::Owned>
+    {
+        new_forwarding_call(self.add_ref(), interface_id, method_id, size_hint)
+    }
+
+    fn call(&self, interface_id: u64, method_id: u16,
+            params: Box<ParamsHook>, results: Box<ResultsHook>) -> Promise<(), Error>
+    {
+        let params = ::capability::Params::new(params);
+        let results = ::capability::Results::new(results);
+        if let Ok(mut server) = self.server.try_borrow_mut() {
+            return server.dispatch_call(interface_id, method_id, params, results);
+        }
+
+        // The server is calling itself. Wait until it has returned, so that it is no longer
+        // borrowed by the time the call's own promise is polled.
+        let server = self.server.clone();
+        Promise::from_future(::futures::future::lazy(move || {
+            server.borrow_mut().dispatch_call(interface_id, method_id, params, results)
+        }))
+    }
+
+    fn get_brand(&self) -> usize {
+        0
+    }
+
+    fn get_ptr(&self) -> usize {
+        &*self.server as *const _ as usize
+    }
+
+    fn get_resolved(&self) -> Option<Box<ClientHook>> {
+        None
+    }
+
+    fn when_more_resolved(&self) -> Option<Promise<Box<ClientHook>, Error>> {
+        None
+    }
+}
+
+impl ServerHook for Client {
+    fn new_client(server: Box<Server>) -> ::capability::Client {
+        ::capability::Client::new(Box::new(Client::new(server)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::Future;
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use any_pointer;
+    use capability::{Params, Promise, Results, Server};
+    use primitive_list;
+    use private::capability::{ClientHook, ServerHook};
+    use Error;
+
+    struct Doubler;
+
+    impl Server for Doubler {
+        fn dispatch_call(&mut self, _interface_id: u64, method_id: u16,
+                         params: Params<any_pointer::Owned>,
+                         mut results: Results<any_pointer::Owned>) -> Promise<(), Error>
+        {
+            if method_id != 0 {
+                return Promise::err(Error::unimplemented("no such method".to_string()));
+            }
+            let value = match params.get().and_then(|p| p.get_as::<primitive_list::Reader<u64>>()) {
+                Ok(list) => list.get(0),
+                Err(e) => return Promise::err(e),
+            };
+            results.get().initn_as::<primitive_list::Builder<u64>>(1).set(0, value * 2);
+            Promise::ok(())
+        }
+    }
+
+    /// Passes every call on to `target` as a tail call.
+    struct Forwarder {
+        target: ::capability::Client,
+    }
+
+    impl Server for Forwarder {
+        fn dispatch_call(&mut self, interface_id: u64, method_id: u16,
+                         params: Params<any_pointer::Owned>,
+                         results: Results<any_pointer::Owned>) -> Promise<(), Error>
+        {
+            let mut request = self.target.new_call::<any_pointer::Owned, any_pointer::Owned>(
+                interface_id, method_id, None);
+            match params.get() {
+                Ok(p) => if let Err(e) = request.set(p) { return Promise::err(e) },
+                Err(e) => return Promise::err(e),
+            }
+            results.hook.tail_call(request.hook)
+        }
+    }
+
+    /// Returns a struct whose only pointer is a new `Doubler`.
+    struct DoublerFactory;
+
+    impl Server for DoublerFactory {
+        fn dispatch_call(&mut self, _interface_id: u64, _method_id: u16,
+                         _params: Params<any_pointer::Owned>,
+                         mut results: Results<any_pointer::Owned>) -> Promise<(), Error>
+        {
+            let doubler = <::local::Client as ServerHook>::new_client(Box::new(Doubler));
+            results.get().init_any_struct(0, 1).get_pointer_field(0).set_as_capability(doubler.hook);
+            Promise::ok(())
+        }
+    }
+
+    /// Logs the method id of every call. Method 0 also calls method 1 on `this`.
+    struct Recorder {
+        log: Rc<RefCell<Vec<u16>>>,
+        this: Rc<RefCell<Option<Box<ClientHook>>>>,
+    }
+
+    impl Server for Recorder {
+        fn dispatch_call(&mut self, interface_id: u64, method_id: u16,
+                         _params: Params<any_pointer::Owned>,
+                         _results: Results<any_pointer::Owned>) -> Promise<(), Error>
+        {
+            self.log.borrow_mut().push(method_id);
+            if method_id != 0 {
+                return Promise::ok(());
+            }
+            let this = match *self.this.borrow() {
+                Some(ref hook) => ::capability::Client::new(hook.add_ref()),
+                None => return Promise::err(Error::failed("no client for this".to_string())),
+            };
+            let request = this.new_call::<any_pointer::Owned, any_pointer::Owned>(interface_id, 1, None);
+            Promise::from_future(request.send().promise.map(|_| ()))
+        }
+    }
+
+    fn new_recorder()
+        -> (::capability::Client, Rc<RefCell<Vec<u16>>>, Rc<RefCell<Option<Box<ClientHook>>>>)
+    {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let this = Rc::new(RefCell::new(None));
+        let client = <::local::Client as ServerHook>::new_client(
+            Box::new(Recorder { log: log.clone(), this: this.clone() }));
+        (client, log, this)
+    }
+
+    fn call(client: &::capability::Client, method_id: u16, value: u64) -> ::Result<u64> {
+        let mut request = client.new_call::<any_pointer::Owned, any_pointer::Owned>(7, method_id, None);
+        request.get().initn_as::<primitive_list::Builder<u64>>(1).set(0, value);
+        let response = try!(request.send().promise.wait());
+        let list = try!(try!(response.get()).get_as::<primitive_list::Reader<u64>>());
+        Ok(list.get(0))
+    }
+
+    #[test]
+    fn local_call() {
+        let client = <::local::Client as ServerHook>::new_client(Box::new(Doubler));
+        assert_eq!(call(&client, 0, 21).unwrap(), 42);
+        assert_eq!(call(&::capability::Client::new(client.hook.add_ref()), 0, 5).unwrap(), 10);
+        let e = call(&client, 1, 0).unwrap_err();
+        assert_eq!(e.kind, ::ErrorKind::Unimplemented);
+    }
+
+    #[test]
+    fn calls_reach_server_in_order() {
+        let (client, log, _) = new_recorder();
+        let promises: Vec<_> = (2..5).map(|method_id| {
+            client.new_call::<any_pointer::Owned, any_pointer::Owned>(7, method_id, None).send().promise
+        }).collect();
+        assert_eq!(*log.borrow(), vec![2, 3, 4]);
+        for promise in promises.into_iter().rev() {
+            promise.wait().unwrap();
+        }
+        assert_eq!(*log.borrow(), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn server_calls_itself() {
+        let (client, log, this) = new_recorder();
+        *this.borrow_mut() = Some(client.hook.add_ref());
+        let promise = client.new_call::<any_pointer::Owned, any_pointer::Owned>(7, 0, None)
+            .send().promise;
+        assert_eq!(*log.borrow(), vec![0]);
+        promise.wait().unwrap();
+        assert_eq!(*log.borrow(), vec![0, 1]);
+        *this.borrow_mut() = None;
+    }
+
+    #[test]
+    fn local_tail_call() {
+        let doubler = <::local::Client as ServerHook>::new_client(Box::new(Doubler));
+        let forwarder = <::local::Client as ServerHook>::new_client(
+            Box::new(Forwarder { target: doubler }));
+        assert_eq!(call(&forwarder, 0, 100).unwrap(), 200);
+        assert_eq!(call(&forwarder, 1, 0).unwrap_err().kind, ::ErrorKind::Unimplemented);
+    }
+
+    #[test]
+    fn local_pipeline() {
+        let factory = <::local::Client as ServerHook>::new_client(Box::new(DoublerFactory));
+        let request = factory.new_call::<any_pointer::Owned, any_pointer::Owned>(7, 0, None);
+        let remote = request.send();
+
+        // Before the factory has run, the pipelined capability queues its calls.
+        let doubler = ::capability::Client::new(remote.pipeline.get_pointer_field(0).as_cap());
+        assert!(doubler.hook.get_resolved().is_none());
+        assert_eq!(call(&doubler, 0, 4).unwrap(), 8);
+
+        // Once the response is in, the capability is looked up directly.
+        let response = remote.promise.wait().unwrap();
+        assert!(!response.get().unwrap().is_null());
+        let doubler = ::capability::Client::new(remote.pipeline.get_pointer_field(0).as_cap());
+        assert_eq!(call(&doubler, 0, 9).unwrap(), 18);
+
+        // Following a pointer that is not there yields a broken capability.
+        let missing = ::capability::Client::new(remote.pipeline.get_pointer_field(3).as_cap());
+        assert!(call(&missing, 0, 1).is_err());
+    }
+}
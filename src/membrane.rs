@@ -0,0 +1,391 @@
+// Copyright (c) 2013-2015 Sandstorm Development Group, Inc. and contributors
+// Licensed under the MIT License:
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Wrappers that run a policy on every call made on a capability.
+//!
+//! A membrane wraps a capability so that each call on it first goes through a `Policy`, which
+//! may let the call through, rewrite its params, or reject it. Capabilities that are passed
+//! across the membrane in params or results are wrapped in the same way, so a capability
+//! obtained through a wrapped one cannot be used to get around the policy. A wrapper that is
+//! passed back across the membrane, the way it came, is unwrapped instead of wrapped again.
+
+use futures::Future;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+use any_pointer;
+use capability::Promise;
+use private::capability::{ClientHook, ParamsHook, ResultsHook};
+use {Error, MessageSize, Result};
+
+/// Which way a call crosses a membrane.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// From outside in: a call on the capability that the membrane was created around, or on
+    /// one that came out of the membrane in the results of an inward call.
+    Inward,
+    /// From inside out: a call on a capability that went into the membrane in the params of
+    /// an inward call.
+    Outward,
+}
+
+impl Direction {
+    fn reverse(self) -> Direction {
+        match self {
+            Direction::Inward => Direction::Outward,
+            Direction::Outward => Direction::Inward,
+        }
+    }
+}
+
+/// Decides what happens to calls made through a membrane.
+pub trait Policy {
+    /// Called before a call is forwarded, with a copy of its params that the policy is free to
+    /// rewrite. Returning an error rejects the call with that error; policies that deny access
+    /// conventionally use `Error::unimplemented()`.
+    fn on_call(&self, direction: Direction, interface_id: u64, method_id: u16,
+               params: any_pointer::Builder) -> Result<()>
+    {
+        let _ = (direction, interface_id, method_id, params);
+        Ok(())
+    }
+
+    /// Called when a forwarded call has returned, with the results that are about to be handed
+    /// back to the caller. Returning an error fails the call.
+    fn on_return(&self, direction: Direction, interface_id: u64, method_id: u16,
+                 results: any_pointer::Builder) -> Result<()>
+    {
+        let _ = (direction, interface_id, method_id, results);
+        Ok(())
+    }
+}
+
+/// What all the wrappers of one membrane share.
+struct Membrane {
+    policy: Rc<Policy>,
+    // The wrappers that are alive, by `get_ptr()`, so that those passed back can be unwrapped.
+    wrappers: RefCell<HashMap<usize, Weak<Inner>>>,
+}
+
+impl Membrane {
+    /// The brand of this membrane's wrappers.
+    fn brand(&self) -> usize {
+        self as *const _ as usize
+    }
+
+    fn new_client(this: &Rc<Membrane>, target: Box<ClientHook>, direction: Direction) -> Client {
+        let inner = Rc::new(Inner { target: target, direction: direction, membrane: this.clone() });
+        this.wrappers.borrow_mut().insert(&*inner as *const _ as usize, Rc::downgrade(&inner));
+        Client { inner: inner }
+    }
+
+    /// Wraps `cap`, which is crossing the membrane such that calls on it go in `direction`.
+    fn wrap(this: &Rc<Membrane>, cap: Box<ClientHook>, direction: Direction) -> Box<ClientHook> {
+        if cap.is_null() {
+            return cap;
+        }
+        if cap.get_brand() == this.brand() {
+            let wrapper = this.wrappers.borrow().get(&cap.get_ptr()).and_then(Weak::upgrade);
+            if let Some(wrapper) = wrapper {
+                if wrapper.direction == direction.reverse() {
+                    // One of ours, going back the way it came.
+                    return wrapper.target.add_ref();
+                }
+            }
+        }
+        Box::new(Membrane::new_client(this, cap, direction))
+    }
+}
+
+struct Inner {
+    target: Box<ClientHook>,
+    direction: Direction,
+    membrane: Rc<Membrane>,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        self.membrane.wrappers.borrow_mut().remove(&(self as *const _ as usize));
+    }
+}
+
+/// A capability whose calls are passed to `target` once its `Policy` has allowed them.
+pub struct Client {
+    inner: Rc<Inner>,
+}
+
+impl Client {
+    /// Wraps `target` in a new membrane, whose policy sees calls on it as `Direction::Inward`.
+    pub fn new(target: Box<ClientHook>, policy: Rc<Policy>) -> Client {
+        let membrane = Rc::new(Membrane { policy: policy, wrappers: RefCell::new(HashMap::new()) });
+        Membrane::new_client(&membrane, target, Direction::Inward)
+    }
+}
+
+impl ClientHook for Client {
+    fn add_ref(&self) -> Box<ClientHook> {
+        Box::new(Client { inner: self.inner.clone() })
+    }
+
+    fn new_call(&self, interface_id: u64, method_id: u16, size_hint: Option<MessageSize>)
+                -> ::capability::Request<any_pointer::Owned, any_pointer::Owned>
+    {
+        ::local::new_forwarding_call(self.add_ref(), interface_id, method_id, size_hint)
+    }
+
+    fn call(&self, interface_id: u64, method_id: u16,
+            params: Box<ParamsHook>, mut results: Box<ResultsHook>) -> Promise<(), Error>
+    {
+        let direction = self.inner.direction;
+        let membrane = self.inner.membrane.clone();
+        let mut request = self.inner.target.new_call(interface_id, method_id, None);
+        let allowed = params.get()
+            .and_then(|params| {
+                request.hook.get().set_with_cap_translator(params, |cap| {
+                    Ok(Some(Membrane::wrap(&membrane, cap, direction.reverse())))
+                })
+            })
+            .and_then(|()| {
+                membrane.policy.on_call(direction, interface_id, method_id, request.hook.get())
+            });
+        if let Err(e) = allowed {
+            return Promise::err(e);
+        }
+        drop(params);
+
+        let call = request.hook.send().promise.and_then(move |response| {
+            let mut builder = try!(results.get());
+            try!(builder.borrow().set_with_cap_translator(try!(response.hook.get()), |cap| {
+                Ok(Some(Membrane::wrap(&membrane, cap, direction)))
+            }));
+            membrane.policy.on_return(direction, interface_id, method_id, builder)
+        });
+        Promise::from_future(call)
+    }
+
+    fn get_brand(&self) -> usize {
+        self.inner.membrane.brand()
+    }
+
+    fn get_ptr(&self) -> usize {
+        &*self.inner as *const _ as usize
+    }
+
+    fn get_resolved(&self) -> Option<Box<ClientHook>> {
+        self.inner.target.get_resolved()
+            .map(|cap| Membrane::wrap(&self.inner.membrane, cap, self.inner.direction))
+    }
+
+    fn when_more_resolved(&self) -> Option<Promise<Box<ClientHook>, Error>> {
+        let membrane = self.inner.membrane.clone();
+        let direction = self.inner.direction;
+        self.inner.target.when_more_resolved().map(|promise| {
+            Promise::from_future(promise.map(move |cap| Membrane::wrap(&membrane, cap, direction)))
+        })
+    }
+
+    fn is_null(&self) -> bool {
+        self.inner.target.is_null()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::Future;
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use any_pointer;
+    use capability::{Params, Promise, Results, Server};
+    use primitive_list;
+    use private::capability::ClientHook;
+    use {Error, ErrorKind, Result};
+    use super::Direction::{Inward, Outward};
+
+    /// Method 0 doubles a number; method 1 returns a new `Doubler` in pointer 0 of a struct;
+    /// method 3 doubles a number by calling method 0 on the capability in pointer 0 of its
+    /// params. Records the `get_ptr()` of the capabilities it hands out and is handed.
+    struct Doubler {
+        caps: Rc<RefCell<Vec<usize>>>,
+    }
+
+    impl Server for Doubler {
+        fn dispatch_call(&mut self, _interface_id: u64, method_id: u16,
+                         params: Params<any_pointer::Owned>,
+                         mut results: Results<any_pointer::Owned>) -> Promise<(), Error>
+        {
+            match method_id {
+                1 => {
+                    let cap = ::local::Client::new(Box::new(Doubler { caps: self.caps.clone() }));
+                    self.caps.borrow_mut().push(cap.get_ptr());
+                    results.get().init_any_struct(0, 1).get_pointer_field(0)
+                        .set_as_capability(Box::new(cap));
+                    Promise::ok(())
+                }
+                3 => {
+                    let cap = match params.get()
+                        .and_then(|p| p.get_as::<::any_struct::Reader>())
+                        .and_then(|st| st.get_pointer_field(0).get_pipelined_cap(&[]))
+                    {
+                        Ok(cap) => cap,
+                        Err(e) => return Promise::err(e),
+                    };
+                    self.caps.borrow_mut().push(cap.get_ptr());
+                    match double(&*cap, 5) {
+                        Ok(value) => {
+                            results.get().initn_as::<primitive_list::Builder<u64>>(1).set(0, value);
+                            Promise::ok(())
+                        }
+                        Err(e) => Promise::err(e),
+                    }
+                }
+                _ => {
+                    let value = match params.get()
+                        .and_then(|p| p.get_as::<primitive_list::Reader<u64>>())
+                    {
+                        Ok(list) => list.get(0),
+                        Err(e) => return Promise::err(e),
+                    };
+                    results.get().initn_as::<primitive_list::Builder<u64>>(1).set(0, value * 2);
+                    Promise::ok(())
+                }
+            }
+        }
+    }
+
+    fn new_doubler() -> (Box<ClientHook>, Rc<RefCell<Vec<usize>>>) {
+        let caps = Rc::new(RefCell::new(Vec::new()));
+        (Box::new(::local::Client::new(Box::new(Doubler { caps: caps.clone() }))), caps)
+    }
+
+    /// Logs every call, rejects method 2, and adds one to every number passed to method 0.
+    struct Audit {
+        log: RefCell<Vec<(super::Direction, u16)>>,
+    }
+
+    impl super::Policy for Audit {
+        fn on_call(&self, direction: super::Direction, _interface_id: u64, method_id: u16,
+                   params: any_pointer::Builder) -> Result<()>
+        {
+            self.log.borrow_mut().push((direction, method_id));
+            match method_id {
+                0 => {
+                    let mut list: primitive_list::Builder<u64> = try!(params.get_as());
+                    let value = list.get(0);
+                    list.set(0, value + 1);
+                    Ok(())
+                }
+                2 => Err(Error::unimplemented("method 2 is not allowed".to_string())),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    fn new_audit() -> Rc<Audit> {
+        Rc::new(Audit { log: RefCell::new(Vec::new()) })
+    }
+
+    fn call(client: &ClientHook, method_id: u16, value: u64)
+            -> Result<::capability::Response<any_pointer::Owned>>
+    {
+        let mut request = client.new_call(0, method_id, None);
+        request.get().initn_as::<primitive_list::Builder<u64>>(1).set(0, value);
+        request.hook.send().promise.wait()
+    }
+
+    fn double(client: &ClientHook, value: u64) -> Result<u64> {
+        let response = try!(call(client, 0, value));
+        let list: primitive_list::Reader<u64> = try!(try!(response.get()).get_as());
+        Ok(list.get(0))
+    }
+
+    /// Calls method 3 on `client`, passing it `cap`.
+    fn double_through(client: &ClientHook, cap: Box<ClientHook>) -> Result<u64> {
+        let mut request = client.new_call(0, 3, None);
+        request.get().init_any_struct(0, 1).get_pointer_field(0).set_as_capability(cap);
+        let response = try!(request.hook.send().promise.wait());
+        let list: primitive_list::Reader<u64> = try!(try!(response.get()).get_as());
+        Ok(list.get(0))
+    }
+
+    fn get_doubler(client: &ClientHook) -> Box<ClientHook> {
+        let response = call(client, 1, 0).unwrap();
+        let st: ::any_struct::Reader = response.get().unwrap().get_as().unwrap();
+        st.get_pointer_field(0).get_pipelined_cap(&[]).unwrap()
+    }
+
+    #[test]
+    fn membrane_runs_policy() {
+        let audit = new_audit();
+        let client = super::Client::new(new_doubler().0, audit.clone());
+
+        assert_eq!(double(&client, 20).unwrap(), 42);
+        assert_eq!(call(&client, 2, 0).err().unwrap().kind, ErrorKind::Unimplemented);
+
+        // The capability in the results is wrapped as well.
+        let inner = get_doubler(&client);
+        assert_eq!(double(&*inner, 1).unwrap(), 4);
+        assert_eq!(call(&*inner, 2, 0).err().unwrap().kind, ErrorKind::Unimplemented);
+
+        assert_eq!(*audit.log.borrow(),
+                   vec![(Inward, 0), (Inward, 2), (Inward, 1), (Inward, 0), (Inward, 2)]);
+    }
+
+    #[test]
+    fn capabilities_in_params_are_called_outward() {
+        let audit = new_audit();
+        let (target, seen) = new_doubler();
+        let client = super::Client::new(target, audit.clone());
+
+        // The target calls the outside doubler through the membrane, so 5 becomes 6 on the way.
+        let (outside, _) = new_doubler();
+        let outside_ptr = outside.get_ptr();
+        assert_eq!(double_through(&client, outside).unwrap(), 12);
+        assert!(seen.borrow()[0] != outside_ptr);
+        assert_eq!(*audit.log.borrow(), vec![(Inward, 3), (Outward, 0)]);
+    }
+
+    #[test]
+    fn wrappers_passed_back_are_unwrapped() {
+        let audit = new_audit();
+        let (target, seen) = new_doubler();
+        let client = super::Client::new(target, audit.clone());
+
+        // The target gets back the very doubler it handed out, and calls it directly.
+        let inner = get_doubler(&client);
+        assert_eq!(double_through(&client, inner).unwrap(), 10);
+        let seen = seen.borrow();
+        assert_eq!(seen[1], seen[0]);
+        assert_eq!(*audit.log.borrow(), vec![(Inward, 1), (Inward, 3)]);
+    }
+
+    #[test]
+    fn references_share_identity() {
+        let (target, _) = new_doubler();
+        let target_ptr = target.get_ptr();
+        let client = super::Client::new(target, new_audit());
+        assert!(client.get_ptr() != target_ptr);
+        assert_eq!(client.add_ref().get_ptr(), client.get_ptr());
+    }
+}
@@ -24,7 +24,7 @@
 use any_pointer;
 use private::arena::{BuilderArenaImpl, ReaderArenaImpl, BuilderArena, ReaderArena};
 use private::layout;
-use traits::{FromPointerReader, FromPointerBuilder, SetPointerBuilder};
+use traits::{FromPointerReader, FromPointerBuilder, SetListElement, SetPointerBuilder};
 use {OutputSegments, Result, Word};
 
 /// Options controlling how data is read.
@@ -62,6 +62,10 @@ pub struct ReaderOptions {
 pub const DEFAULT_READER_OPTIONS: ReaderOptions =
     ReaderOptions { traversal_limit_in_words: 8 * 1024 * 1024, nesting_limit: 64 };
 
+/// How many times over a message can be read after `Reader::validate()` succeeds, in terms of
+/// the traversal budget that the validation itself used.
+pub const VALIDATED_READ_FACTOR: u64 = 8;
+
 
 impl Default for ReaderOptions {
     fn default() -> ReaderOptions {
@@ -119,6 +123,15 @@ impl <S> Reader<S> where S: ReaderSegments {
         }
     }
 
+    /// Attaches the capabilities that the message's capability pointers refer to, by index. A
+    /// transport that carries capabilities alongside messages passes the ones that arrived with
+    /// this message here, in the order of the sender's `Builder::get_cap_table()`.
+    pub fn with_cap_table(self, cap_table: Vec<Option<Box<::private::capability::ClientHook>>>)
+                          -> ReaderWithCaps<S>
+    {
+        ReaderWithCaps { reader: self, cap_table: cap_table }
+    }
+
     fn get_root_internal<'a>(&'a self) -> Result<any_pointer::Reader<'a>> {
         let (segment_start, _seg_len) = try!(self.arena.get_segment(0));
         let pointer_reader = try!(layout::PointerReader::get_root(
@@ -131,18 +144,71 @@ impl <S> Reader<S> where S: ReaderSegments {
         try!(self.get_root_internal()).get_as()
     }
 
+    /// Traverses every pointer reachable from the root once, checking bounds, far-pointer landing
+    /// pads, list element sizes, and the nesting and traversal limits.
+    ///
+    /// If this succeeds, the remaining traversal budget is raised to `VALIDATED_READ_FACTOR`
+    /// times what the validation used, so the whole message can then be read that many times
+    /// over without running out. The limit stays in force beyond that: code that keeps
+    /// re-reading the same shared subtree still fails, as it would without validation.
+    ///
+    /// Validation does not know the schema, so reads can still fail where the schema disagrees
+    /// with the message: a pointer to a list where a struct is expected, or a byte list read as
+    /// text that is not NUL-terminated.
+    pub fn validate(&self) -> Result<()> {
+        let before = self.arena.read_limit();
+        try!(try!(self.get_root_internal()).validate());
+        let after = self.arena.read_limit();
+        let budget = (before - after).saturating_mul(VALIDATED_READ_FACTOR);
+        self.arena.set_read_limit(::std::cmp::max(after, budget));
+        Ok(())
+    }
+
     pub fn into_segments(self) -> S {
         self.arena.into_segments()
     }
 }
 
+/// A `Reader` whose capability pointers refer to the capabilities in a table, as returned by
+/// `Reader::with_cap_table()`. Unlike a `Reader`, it cannot be sent to other threads, as the
+/// capabilities may not be.
+pub struct ReaderWithCaps<S> where S: ReaderSegments {
+    reader: Reader<S>,
+    cap_table: Vec<Option<Box<::private::capability::ClientHook>>>,
+}
+
+impl <S> ReaderWithCaps<S> where S: ReaderSegments {
+    /// The capabilities that the message's capability pointers refer to.
+    pub fn get_cap_table(&self) -> &[Option<Box<::private::capability::ClientHook>>] {
+        &self.cap_table
+    }
+
+    /// Gets the root of the message, interpreting it as the given type.
+    pub fn get_root<'a, T: FromPointerReader<'a>>(&'a self) -> Result<T> {
+        use ::traits::Imbue;
+        let mut root = try!(self.reader.get_root_internal());
+        root.imbue(&self.cap_table);
+        root.get_as()
+    }
+
+    /// See `Reader::validate()`.
+    pub fn validate(&self) -> Result<()> {
+        self.reader.validate()
+    }
+
+    /// Separates the message from its capabilities.
+    pub fn into_parts(self) -> (Reader<S>, Vec<Option<Box<::private::capability::ClientHook>>>) {
+        (self.reader, self.cap_table)
+    }
+}
+
 /// An object that allocates memory for a Cap'n Proto message as it is being built.
 pub unsafe trait Allocator {
     /// Allocates memory for a new segment, returning a pointer to the start of the segment
     /// and a u32 indicating the length of the segment.
     ///
     /// UNSAFETY ALERT: The callee is responsible for ensuring that the returned memory is valid
-    /// for the lifetime of the object and doesn't overlap with other allocated memory.
+    /// for the lifetime of the object, doesn't overlap with other allocated memory, and is zeroed.
     fn allocate_segment(&mut self, minimum_size: u32) -> (*mut Word, u32);
 
     fn pre_drop(&mut self, _segment0_currently_allocated: u32) {}
@@ -213,6 +279,16 @@ impl <A> Builder<A> where A: Allocator {
         root.init_as()
     }
 
+    /// Initializes the root as a list with one element per item of `values`.
+    /// See `any_pointer::Builder::init_from_iter()`.
+    pub fn init_root_from_iter<'a, T, I>(&'a mut self, values: I) -> T
+        where T: FromPointerBuilder<'a> + SetListElement<I::Item>,
+              I: IntoIterator, I::IntoIter: ExactSizeIterator
+    {
+        let root = self.get_root_internal();
+        root.init_from_iter(values)
+    }
+
     /// Gets the root, interpreting it as the given type.
     pub fn get_root<'a, T: FromPointerBuilder<'a>>(&'a mut self) -> Result<T> {
         let root = self.get_root_internal();
@@ -242,6 +318,19 @@ impl <A> Builder<A> where A: Allocator {
     pub fn get_segments_for_output<'a>(&'a self) -> OutputSegments<'a> {
         self.arena.get_segments_for_output()
     }
+
+    /// The capabilities that have been set in the message, indexed by the capability pointers
+    /// that refer to them. A transport that carries capabilities alongside messages sends these
+    /// along with the segments, for the receiver to pass to `Reader::with_cap_table()`.
+    pub fn get_cap_table(&self) -> &[Option<Box<::private::capability::ClientHook>>] {
+        &self.cap_table
+    }
+
+    /// Like `get_cap_table()`, but allows the capabilities to be replaced, e.g. with proxies,
+    /// without changing the pointers that refer to them.
+    pub fn get_cap_table_mut(&mut self) -> &mut [Option<Box<::private::capability::ClientHook>>] {
+        &mut self.cap_table
+    }
 }
 
 #[derive(Debug)]
@@ -306,7 +395,12 @@ pub struct ScratchSpace<'a> {
 }
 
 impl <'a> ScratchSpace<'a> {
+    /// Zeroes `slice`, which is then handed out as the first segment of each message built in
+    /// this scratch space.
     pub fn new(slice: &'a mut [Word]) -> ScratchSpace<'a> {
+        unsafe {
+            ::std::ptr::write_bytes(slice.as_mut_ptr(), 0u8, slice.len());
+        }
         ScratchSpace { slice: slice, in_use: false }
     }
 }
@@ -0,0 +1,275 @@
+// Copyright (c) 2013-2015 Sandstorm Development Group, Inc. and contributors
+// Licensed under the MIT License:
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Hooks for tracing the calls made on a capability, e.g. to collect per-method latencies.
+//!
+//! `observe()` wraps a `capability::Client` so that an `Observer` hears about each call made
+//! through it. Because generated clients hold a `capability::Client`, they can be observed
+//! by wrapping that field, without any change to the generated code.
+
+use futures::Future;
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Instant;
+
+use any_pointer;
+use capability::Promise;
+use private::capability::{ClientHook, ParamsHook, ResultsHook, SharedHook};
+use {Error, ErrorKind, MessageSize};
+
+/// Describes a call that has just been made.
+#[derive(Clone, Copy, Debug)]
+pub struct CallStarted {
+    /// Tells calls made through the same observed capability apart. Matches the `call_id` of
+    /// the call's `CallFinished` event.
+    pub call_id: u64,
+    pub interface_id: u64,
+    pub method_id: u16,
+
+    /// The size of the params. Zero if the params could not be read.
+    pub params_size: MessageSize,
+    pub time: Instant,
+}
+
+/// Describes a call that has returned, failed, or been canceled.
+#[derive(Clone, Copy, Debug)]
+pub struct CallFinished {
+    pub call_id: u64,
+    pub interface_id: u64,
+    pub method_id: u16,
+    pub started: Instant,
+    pub finished: Instant,
+
+    /// The kind of error that the call failed with, if it failed.
+    pub error: Option<ErrorKind>,
+
+    /// True if the caller dropped the call's promise before it finished.
+    pub canceled: bool,
+}
+
+/// Receives the events of the calls made through an observed capability.
+pub trait Observer {
+    fn on_call_started(&self, event: &CallStarted) {
+        let _ = event;
+    }
+
+    fn on_call_finished(&self, event: &CallFinished) {
+        let _ = event;
+    }
+}
+
+/// Wraps `client` so that `observer` hears about every call made through the result.
+/// Capabilities obtained through calls on the result are not observed.
+pub fn observe(client: ::capability::Client, observer: Rc<Observer>) -> ::capability::Client {
+    ::capability::Client::new(Box::new(Client {
+        target: SharedHook::new(client.hook),
+        observer: observer,
+        next_call_id: Rc::new(Cell::new(0)),
+    }))
+}
+
+/// Reports the end of a call, as canceled if it is dropped before `finish()` is called.
+struct PendingCall {
+    observer: Rc<Observer>,
+    event: CallStarted,
+    done: bool,
+}
+
+impl PendingCall {
+    fn report(&mut self, error: Option<ErrorKind>, canceled: bool) {
+        self.done = true;
+        self.observer.on_call_finished(&CallFinished {
+            call_id: self.event.call_id,
+            interface_id: self.event.interface_id,
+            method_id: self.event.method_id,
+            started: self.event.time,
+            finished: Instant::now(),
+            error: error,
+            canceled: canceled,
+        });
+    }
+
+    fn finish(mut self, result: &Result<(), Error>) {
+        self.report(result.as_ref().err().map(|e| e.kind), false);
+    }
+}
+
+impl Drop for PendingCall {
+    fn drop(&mut self) {
+        if !self.done {
+            self.report(None, true);
+        }
+    }
+}
+
+struct Client {
+    target: SharedHook,
+    observer: Rc<Observer>,
+    next_call_id: Rc<Cell<u64>>,
+}
+
+impl Client {
+    /// Observes `cap` as part of this capability, e.g. once it has resolved to `cap`.
+    fn wrap(&self, cap: Box<ClientHook>) -> Box<ClientHook> {
+        Box::new(Client {
+            target: SharedHook::new(cap),
+            observer: self.observer.clone(),
+            next_call_id: self.next_call_id.clone(),
+        })
+    }
+}
+
+impl ClientHook for Client {
+    fn add_ref(&self) -> Box<ClientHook> {
+        Box::new(Client {
+            target: self.target.clone(),
+            observer: self.observer.clone(),
+            next_call_id: self.next_call_id.clone(),
+        })
+    }
+
+    fn new_call(&self, interface_id: u64, method_id: u16, size_hint: Option<MessageSize>)
+                -> ::capability::Request<any_pointer::Owned, any_pointer::Owned>
+    {
+        ::local::new_forwarding_call(self.add_ref(), interface_id, method_id, size_hint)
+    }
+
+    fn call(&self, interface_id: u64, method_id: u16,
+            params: Box<ParamsHook>, results: Box<ResultsHook>) -> Promise<(), Error>
+    {
+        let call_id = self.next_call_id.get();
+        self.next_call_id.set(call_id + 1);
+        let params_size = params.get().and_then(|params| params.total_size())
+            .unwrap_or(MessageSize { word_count: 0, cap_count: 0 });
+        let event = CallStarted {
+            call_id: call_id,
+            interface_id: interface_id,
+            method_id: method_id,
+            params_size: params_size,
+            time: Instant::now(),
+        };
+        self.observer.on_call_started(&event);
+
+        let pending = PendingCall { observer: self.observer.clone(), event: event, done: false };
+        let call = self.target.call(interface_id, method_id, params, results);
+        Promise::from_future(call.then(move |result| {
+            pending.finish(&result);
+            result
+        }))
+    }
+
+    fn get_brand(&self) -> usize {
+        0
+    }
+
+    fn get_ptr(&self) -> usize {
+        self.target.identity()
+    }
+
+    fn get_resolved(&self) -> Option<Box<ClientHook>> {
+        self.target.get_resolved().map(|cap| self.wrap(cap))
+    }
+
+    fn when_more_resolved(&self) -> Option<Promise<Box<ClientHook>, Error>> {
+        let this = Client {
+            target: self.target.clone(),
+            observer: self.observer.clone(),
+            next_call_id: self.next_call_id.clone(),
+        };
+        self.target.when_more_resolved().map(move |promise| {
+            Promise::from_future(promise.map(move |cap| this.wrap(cap)))
+        })
+    }
+
+    fn is_null(&self) -> bool {
+        self.target.is_null()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::Future;
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use any_pointer;
+    use capability::Promise;
+    use primitive_list;
+    use private::test_server;
+    use {Error, ErrorKind};
+    use super::{CallFinished, CallStarted};
+
+    #[derive(Default)]
+    struct Recorder {
+        started: RefCell<Vec<CallStarted>>,
+        finished: RefCell<Vec<CallFinished>>,
+    }
+
+    impl super::Observer for Recorder {
+        fn on_call_started(&self, event: &CallStarted) {
+            self.started.borrow_mut().push(*event);
+        }
+
+        fn on_call_finished(&self, event: &CallFinished) {
+            self.finished.borrow_mut().push(*event);
+        }
+    }
+
+    fn call(client: &::capability::Client, method_id: u16, params_len: u32)
+            -> Promise<(), Error>
+    {
+        let mut request = client.new_call::<any_pointer::Owned, any_pointer::Owned>(
+            0x1234, method_id, None);
+        request.get().initn_as::<primitive_list::Builder<u64>>(params_len);
+        Promise::from_future(request.send().promise.map(|_| ()))
+    }
+
+    #[test]
+    fn observe_calls() {
+        let recorder = Rc::new(Recorder::default());
+        let (server, _pending) = test_server::new_client();
+        let server_ptr = server.hook.get_ptr();
+        let client = super::observe(server, recorder.clone());
+        assert!(client.hook.get_ptr() != server_ptr);
+        assert_eq!(client.hook.add_ref().get_ptr(), client.hook.get_ptr());
+
+        call(&client, 0, 3).wait().unwrap();
+        call(&client, 1, 0).wait().unwrap_err();
+        let pending = call(&client, 2, 0);
+        assert_eq!(recorder.finished.borrow().len(), 2);
+        drop(pending);
+
+        let started = recorder.started.borrow();
+        assert_eq!(started.iter().map(|e| (e.call_id, e.method_id)).collect::<Vec<_>>(),
+                   vec![(0, 0), (1, 1), (2, 2)]);
+        assert_eq!(started[0].interface_id, 0x1234);
+        assert_eq!(started[0].params_size.word_count, 3);
+        assert_eq!(started[1].params_size.word_count, 0);
+
+        let finished = recorder.finished.borrow();
+        assert_eq!(finished.iter().map(|e| (e.call_id, e.error, e.canceled)).collect::<Vec<_>>(),
+                   vec![(0, None, false), (1, Some(ErrorKind::Unimplemented), false),
+                        (2, None, true)]);
+        assert!(finished[0].finished >= finished[0].started);
+    }
+}
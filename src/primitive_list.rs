@@ -21,10 +21,10 @@
 
 //! List of primitives.
 
-use std::{marker, ptr};
+use std::{marker, mem, ptr, slice};
 
 use traits::{FromPointerReader, FromPointerBuilder, IndexMove, ListIter};
-use private::layout::{ListReader, ListBuilder, PointerReader, PointerBuilder,
+use private::layout::{ElementSize, ListReader, ListBuilder, PointerReader, PointerBuilder,
                       PrimitiveElement};
 use Result;
 
@@ -57,6 +57,34 @@ impl <'a, T: PrimitiveElement> Reader<'a, T> {
     }
 }
 
+/// Whether a list with the given step can be viewed as a `[T]` in place: `T` must be a
+/// byte-aligned numeric type, the elements must be packed back to back, and the wire format's
+/// little-endian encoding must match the target's.
+fn can_view_as_slice<T: PrimitiveElement>(step: u32, ptr: *const u8) -> bool {
+    cfg!(target_endian = "little") &&
+        matches!(T::element_size(), ElementSize::Byte | ElementSize::TwoBytes |
+                                    ElementSize::FourBytes | ElementSize::EightBytes) &&
+        step as usize == mem::size_of::<T>() * 8 &&
+        ptr as usize & (mem::align_of::<T>() - 1) == 0
+}
+
+impl <'a, T: PrimitiveElement> Reader<'a, T> {
+    /// Returns the elements as a slice, without copying, when the list is stored exactly as
+    /// `[T]` would be in memory. Returns `None` on big-endian targets, for misaligned data, and
+    /// for struct lists whose elements are larger than a `T`. A struct list whose elements are
+    /// exactly one `T` wide is viewed in place, each element being its struct's first field.
+    pub fn as_slice(&self) -> Option<&'a [T]> {
+        let bytes = self.reader.into_raw_bytes();
+        if self.len() == 0 {
+            Some(&[])
+        } else if can_view_as_slice::<T>(self.reader.get_step_size_in_bits(), bytes.as_ptr()) {
+            Some(unsafe { slice::from_raw_parts(bytes.as_ptr() as *const T, self.len() as usize) })
+        } else {
+            None
+        }
+    }
+}
+
 impl <'a, T: PrimitiveElement> FromPointerReader<'a> for Reader<'a, T> {
     fn get_from_pointer(reader: &PointerReader<'a>) -> Result<Reader<'a, T>> {
         Ok(Reader { reader: try!(reader.get_list(T::element_size(), ptr::null())),
@@ -89,6 +117,17 @@ impl <'a, T> Builder<'a, T> where T: PrimitiveElement {
 
     pub fn len(&self) -> u32 { self.builder.len() }
 
+    /// Drops every element from index `len` on. Errors if `len` exceeds the length.
+    pub fn truncate(&mut self, len: u32) -> Result<()> {
+        self.builder.truncate(len)
+    }
+
+    /// Grows or shrinks the list to `len` elements, padding with zeros. Growing may move the
+    /// list, after which builders obtained for it earlier must not be used.
+    pub fn resize(&mut self, len: u32) -> Result<()> {
+        self.builder.resize(len)
+    }
+
     pub fn as_reader(self) -> Reader<'a, T> {
         Reader {
             marker: marker::PhantomData,
@@ -99,6 +138,34 @@ impl <'a, T> Builder<'a, T> where T: PrimitiveElement {
     pub fn set(&mut self, index: u32, value: T) {
         PrimitiveElement::set(&self.builder, index, value);
     }
+
+    /// Sets every element from `values`, which must have the same length as the list.
+    /// Uses a single memcpy when the list can be viewed as a slice; see `as_slice_mut()`.
+    pub fn copy_from_slice(&mut self, values: &[T]) where T: Copy {
+        assert_eq!(values.len(), self.len() as usize);
+        match self.as_slice_mut() {
+            Some(slice) => slice.copy_from_slice(values),
+            None => {
+                for (idx, value) in values.iter().enumerate() {
+                    PrimitiveElement::set(&self.builder, idx as u32, *value);
+                }
+            }
+        }
+    }
+
+    /// Mutable counterpart of `Reader::as_slice()`.
+    pub fn as_slice_mut(&mut self) -> Option<&mut [T]> {
+        let len = self.len() as usize;
+        let step = self.builder.get_step_size_in_bits();
+        let bytes = self.builder.borrow().into_raw_bytes();
+        if len == 0 {
+            Some(&mut [])
+        } else if can_view_as_slice::<T>(step, bytes.as_ptr()) {
+            Some(unsafe { slice::from_raw_parts_mut(bytes.as_mut_ptr() as *mut T, len) })
+        } else {
+            None
+        }
+    }
 }
 
 impl <'a, T: PrimitiveElement> FromPointerBuilder<'a> for Builder<'a, T> {
@@ -119,6 +186,12 @@ impl <'a, T : PrimitiveElement> Builder<'a, T> {
     }
 }
 
+impl <'a, T: PrimitiveElement> ::traits::SetListElement<T> for Builder<'a, T> {
+    fn set_element(&mut self, index: u32, value: T) {
+        self.set(index, value)
+    }
+}
+
 impl <'a, T> ::traits::SetPointerBuilder<Builder<'a, T>> for Reader<'a, T>
     where T: PrimitiveElement
 {
@@ -128,3 +201,60 @@ impl <'a, T> ::traits::SetPointerBuilder<Builder<'a, T>> for Reader<'a, T>
     }
 }
 
+
+#[test]
+fn as_slice() {
+    let mut message = ::message::Builder::new_default();
+    {
+        let mut list: Builder<u32> = message.init_root::<::any_pointer::Builder>().initn_as(4);
+        for idx in 0..4 {
+            list.set(idx, idx * 10);
+        }
+        list.as_slice_mut().unwrap()[3] = 7;
+    }
+
+    {
+        let list: Reader<u32> = message.get_root_as_reader().unwrap();
+        if cfg!(target_endian = "little") {
+            assert_eq!(list.as_slice(), Some(&[0u32, 10, 20, 7][..]));
+        } else {
+            assert_eq!(list.as_slice(), None);
+        }
+    }
+
+    {
+        let bits: Builder<bool> = message.init_root::<::any_pointer::Builder>().initn_as(4);
+        assert!(bits.as_reader().as_slice().is_none());
+    }
+
+    {
+        let root: ::any_pointer::Builder = message.init_root();
+        root.init_any_struct_list(3, 1, 0);
+    }
+    // Read as a primitive list, a struct list's elements are spaced a whole struct apart.
+    let upgraded: Reader<u32> = message.get_root_as_reader().unwrap();
+    assert_eq!(upgraded.len(), 3);
+    assert_eq!(upgraded.as_slice(), None);
+
+    {
+        let root: ::any_pointer::Builder = message.init_root();
+        let mut structs = root.init_any_struct_list(2, 1, 0);
+        structs.borrow().get_struct_element(1).get_data_section()[0] = 5;
+    }
+    // Structs exactly one u64 wide are laid out as a [u64] would be.
+    let upgraded: Reader<u64> = message.get_root_as_reader().unwrap();
+    if cfg!(target_endian = "little") {
+        assert_eq!(upgraded.as_slice(), Some(&[0u64, 5][..]));
+    }
+}
+
+#[test]
+fn copy_from_slice() {
+    let mut message = ::message::Builder::new_default();
+    {
+        let mut list: Builder<i16> = message.init_root::<::any_pointer::Builder>().initn_as(3);
+        list.copy_from_slice(&[-1, 2, -3]);
+    }
+    let list: Reader<i16> = message.get_root_as_reader().unwrap();
+    assert_eq!(list.iter().collect::<Vec<_>>(), vec![-1, 2, -3]);
+}
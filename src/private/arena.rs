@@ -25,7 +25,7 @@ use std::u64;
 use private::units::*;
 use message;
 use message::{Allocator, ReaderSegments};
-use {Error, OutputSegments, Result, Word};
+use {Error, ErrorReason, OutputSegments, Result, Word};
 
 pub type SegmentId = u32;
 
@@ -42,7 +42,7 @@ impl ReadLimiter {
     pub fn can_read(&self, amount: u64) -> Result<()> {
         let current = self.limit.get();
         if amount > current {
-            Err(Error::failed(format!("read limit exceeded")))
+            Err(Error::with_reason(ErrorReason::ReadLimitExceeded, format!("read limit exceeded")))
         } else {
             self.limit.set(current - amount);
             Ok(())
@@ -77,6 +77,15 @@ impl <S> ReaderArenaImpl <S> where S: ReaderSegments {
         }
     }
 
+    /// The number of words that can still be traversed.
+    pub fn read_limit(&self) -> u64 {
+        self.read_limiter.limit.get()
+    }
+
+    pub fn set_read_limit(&self, limit: u64) {
+        self.read_limiter.limit.set(limit);
+    }
+
     pub fn into_segments(self) -> S {
         self.segments
     }
@@ -86,7 +95,7 @@ impl <S> ReaderArena for ReaderArenaImpl<S> where S: ReaderSegments {
     fn get_segment<'a>(&'a self, id: u32) -> Result<(*const Word, u32)> {
         match self.segments.get_segment(id) {
             Some(seg) => Ok((seg.as_ptr(), seg.len() as u32)),
-            None => Err(Error::failed(format!("Invalid segment id: {}", id))),
+            None => Err(Error::with_reason(ErrorReason::InvalidSegmentId, format!("Invalid segment id: {}", id))),
         }
     }
 
@@ -96,7 +105,7 @@ impl <S> ReaderArena for ReaderArenaImpl<S> where S: ReaderSegments {
         let this_end: usize = this_begin + (segment_len as usize * 8);
 
         if !(from as usize >= this_begin && to as usize <= this_end && from as usize <= to as usize) {
-            Err(Error::failed(format!("message contained out-of-bounds pointer")))
+            Err(Error::with_reason(ErrorReason::OutOfBoundsPointer, format!("message contained out-of-bounds pointer")))
         } else {
             self.read_limiter.can_read((to as usize - from as usize) as u64 / BYTES_PER_WORD as u64)
         }
@@ -116,6 +125,11 @@ pub trait BuilderArena: ReaderArena {
     // https://botbot.me/mozilla/rust/2017-01-31/?msg=80228117&page=19 .)
     fn allocate(&self, segment_id: u32, amount: WordCount32) -> Option<u32>;
     fn allocate_anywhere(&self, amount: u32) -> (SegmentId, u32);
+
+    /// Grows the allocation that ends at word `end` of the segment by `amount` words, which
+    /// succeeds only if nothing has been allocated after it and the segment has room.
+    fn try_extend(&self, segment_id: u32, end: u32, amount: WordCount32) -> bool;
+
     fn get_segment_mut(&self, id: u32) -> (*mut Word, u32);
     fn as_reader<'a>(&'a self) -> &'a ReaderArena;
 }
@@ -209,6 +223,16 @@ impl <A> BuilderArenaImplInner<A> where A: Allocator {
         }
     }
 
+    fn try_extend(&mut self, segment_id: u32, end: u32, amount: WordCount32) -> bool {
+        let allocated = self.allocated[segment_id as usize];
+        if allocated != end || amount > self.get_segment_mut(segment_id).1 - allocated {
+            false
+        } else {
+            self.allocated[segment_id as usize] += amount;
+            true
+        }
+    }
+
     fn allocate_anywhere(&mut self, amount: u32) -> (SegmentId, u32) {
         // first try the existing segments, then try allocating a new segment.
         let allocated_len = self.allocated.len() as u32;
@@ -241,6 +265,10 @@ impl <A> BuilderArena for BuilderArenaImpl<A> where A: Allocator {
         self.inner.borrow_mut().allocate_anywhere(amount)
     }
 
+    fn try_extend(&self, segment_id: u32, end: u32, amount: WordCount32) -> bool {
+        self.inner.borrow_mut().try_extend(segment_id, end, amount)
+    }
+
     fn get_segment_mut(&self, id: u32) -> (*mut Word, u32) {
         self.inner.borrow_mut().get_segment_mut(id)
     }
@@ -262,7 +290,7 @@ pub struct NullArena;
 
 impl ReaderArena for NullArena {
     fn get_segment(&self, _id: u32) -> Result<(*const Word, u32)> {
-        Err(Error::failed(format!("tried to read from null arena")))
+        Err(Error::with_reason(ErrorReason::InvalidSegmentId, format!("tried to read from null arena")))
     }
 
     fn contains_interval(&self, _id: u32, _from: *const Word, _to: *const Word) -> Result<()> {
@@ -283,6 +311,10 @@ impl BuilderArena for NullArena {
         panic!("tried to allocate from a null arena")
     }
 
+    fn try_extend(&self, _segment_id: u32, _end: u32, _amount: WordCount32) -> bool {
+        false
+    }
+
     fn get_segment_mut(&self, _id: u32) -> (*mut Word, u32) {
         (::std::ptr::null_mut(), 0)
     }
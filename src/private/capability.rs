@@ -19,9 +19,24 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 // THE SOFTWARE.
 
+use std::rc::Rc;
+
 use any_pointer;
 use MessageSize;
 use capability::{Params, Promise, Request, RemotePromise, Results};
+use message::{self, HeapAllocator};
+
+/// Allocates the message for a call's params or results, with a first segment big enough for
+/// `size_hint` if one is given.
+pub fn new_message(size_hint: Option<MessageSize>) -> message::Builder<HeapAllocator> {
+    match size_hint {
+        Some(size) => {
+            let words = ::std::cmp::min(size.word_count + 1, u32::MAX as u64) as u32;
+            message::Builder::new(HeapAllocator::new().first_segment_words(words))
+        }
+        None => message::Builder::new_default(),
+    }
+}
 
 pub trait ResponseHook {
     fn get<'a>(&'a self) -> ::Result<any_pointer::Reader<'a>>;
@@ -63,6 +78,11 @@ pub trait ClientHook {
     /// should eventually produce a settled client.
     fn when_more_resolved(&self) -> Option<::capability::Promise<Box<ClientHook>, ::Error>>;
 
+    /// Returns true if this is the capability that a null capability pointer stands for.
+    fn is_null(&self) -> bool {
+        false
+    }
+
     /// Repeatedly calls whenMoreResolved() until it returns nullptr.
     #[cfg(feature = "rpc")]
     fn when_resolved(&self) -> Promise<(), ::Error> {
@@ -87,6 +107,29 @@ impl Clone for Box<ClientHook> {
     }
 }
 
+/// The capability that a wrapping `ClientHook` forwards to. The wrapper's `add_ref()` clones
+/// this rather than the capability, so that every reference to the wrapper shares it, and its
+/// address, `identity()`, can serve as the wrapper's `get_ptr()`.
+#[derive(Clone)]
+pub struct SharedHook(Rc<Box<ClientHook>>);
+
+impl SharedHook {
+    pub fn new(hook: Box<ClientHook>) -> SharedHook {
+        SharedHook(Rc::new(hook))
+    }
+
+    pub fn identity(&self) -> usize {
+        &*self.0 as *const _ as usize
+    }
+}
+
+impl ::std::ops::Deref for SharedHook {
+    type Target = ClientHook;
+    fn deref(&self) -> &(ClientHook + 'static) {
+        &**self.0
+    }
+}
+
 pub trait ServerHook: 'static {
     fn new_client(server: Box<::capability::Server>) -> ::capability::Client;
 }
@@ -21,6 +21,7 @@
 
 use std::mem;
 use std::ptr;
+use std::slice;
 
 use data;
 use text;
@@ -30,12 +31,13 @@ use private::endian::{WireValue, Endian};
 use private::mask::Mask;
 use private::units::*;
 use private::zero;
-use {MessageSize, Result, Word};
+use traits::{IndexMove, ListIter};
+use {Error, MessageSize, Result, Word};
 
 pub use self::ElementSize::{Void, Bit, Byte, TwoBytes, FourBytes, EightBytes, Pointer, InlineComposite};
 
 #[repr(u8)]
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ElementSize {
     Void = 0,
     Bit = 1,
@@ -346,14 +348,21 @@ mod wire_helpers {
     use private::capability::ClientHook;
     use private::arena::*;
     use private::layout::{
-        CapTableBuilder, CapTableReader, ElementSize, ListBuilder, ListReader,
-        StructBuilder, StructReader, StructSize, WirePointer, WirePointerKind};
+        CapTableBuilder, CapTableReader, CapTranslator, ElementSize, ListBuilder, ListReader,
+        PointerBuilder, PointerReader, StructBuilder, StructReader, StructSize, WirePointer,
+        WirePointerKind};
     use private::layout::{data_bits_per_element, pointers_per_element};
     use private::layout::ElementSize::*;
     use private::units::*;
+    use any_pointer;
     use data;
     use text;
-    use {Error, MessageSize, Result, Word};
+    use {Error, ErrorLocation, ErrorReason, MessageSize, PathStep, Result, Word};
+
+    /// The `CapTranslator` used by plain copies: every capability is carried over as is.
+    pub fn keep_cap(cap: Box<ClientHook>) -> Result<Option<Box<ClientHook>>> {
+        Ok(Some(cap))
+    }
 
     pub struct SegmentAnd<T> {
         #[allow(dead_code)]
@@ -361,6 +370,55 @@ mod wire_helpers {
         pub value: T,
     }
 
+    /// Records where a recursive traversal failed, so that its errors can say where the
+    /// offending pointer lives. Nothing is recorded while the traversal succeeds; each level
+    /// adds its step only as an error passes back through it, so steps arrive innermost first.
+    pub struct TraversalPath {
+        steps: Vec<(PathStep, u32, *const WirePointer)>,
+    }
+
+    impl TraversalPath {
+        pub fn new() -> TraversalPath {
+            TraversalPath { steps: Vec::new() }
+        }
+
+        /// Notes that `error` was raised below `step`, and passes it on.
+        #[cold]
+        pub fn record(&mut self, step: PathStep, segment_id: u32, reff: *const WirePointer,
+                      error: Error) -> Error {
+            self.steps.push((step, segment_id, reff));
+            error
+        }
+
+        /// Sets the error's location to the innermost pointer recorded, along with the path
+        /// leading to it.
+        pub fn annotate(&self, arena: &ReaderArena, mut error: Error) -> Error {
+            let (segment_id, reff) = match self.steps.first() {
+                Some(&(_, segment_id, reff)) => (segment_id, reff),
+                None => return error,
+            };
+            error = at_location(arena, segment_id, reff, error);
+            if let Some(ref mut location) = error.location {
+                location.path = self.steps.iter().rev().map(|&(step, _, _)| step).collect();
+            }
+            error
+        }
+    }
+
+    /// Sets the error's location to the segment id and word offset of `reff`.
+    pub fn at_location(arena: &ReaderArena, segment_id: u32, reff: *const WirePointer,
+                       mut error: Error) -> Error {
+        if let Ok((segment_start, _)) = arena.get_segment(segment_id) {
+            let offset = (reff as usize).wrapping_sub(segment_start as usize) / BYTES_PER_WORD;
+            error.location = Some(ErrorLocation {
+                segment_id: segment_id,
+                word_offset: offset as u32,
+                path: Vec::new(),
+            });
+        }
+        error
+    }
+
     #[inline]
     pub fn round_bytes_up_to_words(bytes: ByteCount32) -> WordCount32 {
         //# This code assumes 64-bit words.
@@ -402,16 +460,16 @@ mod wire_helpers {
         arena: &BuilderArena,
         reff: *mut WirePointer,
         segment_id: u32,
-        amount: WordCount32, kind: WirePointerKind) -> (*mut Word, *mut WirePointer, u32)
+        amount: WordCount32, kind: WirePointerKind) -> Result<(*mut Word, *mut WirePointer, u32)>
     {
         let is_null = (*reff).is_null();
         if !is_null {
-            zero_object(arena, segment_id, reff)
+            try!(zero_object(arena, segment_id, reff));
         }
 
         if amount == 0 && kind == WirePointerKind::Struct {
             (*reff).set_kind_and_target_for_empty_struct();
-            return (reff as *mut _, reff, segment_id);
+            return Ok((reff as *mut _, reff, segment_id));
         }
 
         match arena.allocate(segment_id, amount) {
@@ -437,13 +495,13 @@ mod wire_helpers {
 
                 let ptr1 = ptr.offset(POINTER_SIZE_IN_WORDS as isize);
                 (*reff).set_kind_and_target(kind, ptr1);
-                return (ptr1, reff, segment_id);
+                return Ok((ptr1, reff, segment_id));
             }
             Some(idx) => {
                 let (seg_start, _seg_len) = arena.get_segment_mut(segment_id);
                 let ptr: *mut Word = seg_start.offset(idx as isize);
                 (*reff).set_kind_and_target(kind, ptr);
-                return (ptr, reff, segment_id);
+                return Ok((ptr, reff, segment_id));
             }
         }
     }
@@ -512,6 +570,11 @@ mod wire_helpers {
                 //# followed by a tag describing the pointed-to
                 //# object.
 
+                if (*pad).kind() != WirePointerKind::Far {
+                    return Err(Error::with_reason(ErrorReason::MalformedFarPointer,
+                        "Second word of double-far pad must be far pointer.".to_string()));
+                }
+
                 let reff = pad.offset(1);
 
                 let segment_id = (*pad).far_ref().segment_id.get();
@@ -527,14 +590,14 @@ mod wire_helpers {
     pub unsafe fn zero_object(
         arena: &BuilderArena,
         segment_id: u32,
-        reff: *mut WirePointer)
+        reff: *mut WirePointer) -> Result<()>
     {
         //# Zero out the pointed-to object. Use when the pointer is
         //# about to be overwritten making the target object no longer
         //# reachable.
 
         match (*reff).kind() {
-            WirePointerKind::Struct | WirePointerKind::List | WirePointerKind::Other => {
+            WirePointerKind::Struct | WirePointerKind::List => {
                 zero_object_helper(arena, segment_id, reff, (*reff).mut_target())
             }
             WirePointerKind::Far => {
@@ -544,21 +607,34 @@ mod wire_helpers {
                     seg_start.offset((*reff).far_position_in_segment() as isize) as *mut _;
 
                 if (*reff).is_double_far() {
+                    if (*pad).kind() != WirePointerKind::Far {
+                        return Err(Error::with_reason(ErrorReason::MalformedFarPointer,
+                            "Second word of double-far pad must be far pointer.".to_string()));
+                    }
                     let segment_id = (*pad).far_ref().segment_id.get();
 
                     let (seg_start, _seg_len) = arena.get_segment_mut(segment_id);
                     let ptr: *mut Word = seg_start.offset((*pad).far_position_in_segment() as isize);
-                    zero_object_helper(arena,
-                                       segment_id,
-                                       pad.offset(1),
-                                       ptr);
+                    try!(zero_object_helper(arena,
+                                            segment_id,
+                                            pad.offset(1),
+                                            ptr));
 
                     ptr::write_bytes(pad, 0u8, 2);
 
                 } else {
-                    zero_object(arena, segment_id, pad);
+                    try!(zero_object(arena, segment_id, pad));
                     ptr::write_bytes(pad, 0u8, 1);
                 }
+                Ok(())
+            }
+            WirePointerKind::Other => {
+                if (*reff).is_capability() {
+                    // Nothing to zero; the pointer itself is overwritten by the caller.
+                    Ok(())
+                } else {
+                    Err(Error::with_reason(ErrorReason::UnknownPointerType, "Unknown pointer type.".to_string()))
+                }
             }
         }
     }
@@ -567,17 +643,19 @@ mod wire_helpers {
         arena: &BuilderArena,
         segment_id: u32,
         tag: *mut WirePointer,
-        ptr: *mut Word)
+        ptr: *mut Word) -> Result<()>
     {
         match (*tag).kind() {
-            WirePointerKind::Other => { panic!("Don't know how to handle OTHER") }
+            WirePointerKind::Other => {
+                return Err(Error::with_reason(ErrorReason::UnknownPointerType, "Don't know how to handle OTHER".to_string()));
+            }
             WirePointerKind::Struct => {
                 let pointer_section: *mut WirePointer =
                     ptr.offset((*tag).struct_ref().data_size.get() as isize) as *mut _;
 
                 let count = (*tag).struct_ref().ptr_count.get() as isize;
                 for i in 0..count {
-                    zero_object(arena, segment_id, pointer_section.offset(i));
+                    try!(zero_object(arena, segment_id, pointer_section.offset(i)));
                 }
                 ptr::write_bytes(ptr, 0u8, (*tag).struct_ref().word_size() as usize);
             }
@@ -587,23 +665,25 @@ mod wire_helpers {
                     Bit | Byte | TwoBytes | FourBytes | EightBytes => {
                         ptr::write_bytes(
                             ptr, 0u8,
-                            round_bits_up_to_words((
-                                    (*tag).list_ref().element_count() *
-                                        data_bits_per_element(
-                                        (*tag).list_ref().element_size())) as u64) as usize)
+                            round_bits_up_to_words(
+                                (*tag).list_ref().element_count() as u64 *
+                                    data_bits_per_element(
+                                        (*tag).list_ref().element_size()) as u64) as usize)
                     }
                     Pointer => {
                         let count = (*tag).list_ref().element_count() as usize;
                         for i in 0..count as isize {
-                            zero_object(arena, segment_id, ptr.offset(i) as *mut _);
+                            try!(zero_object(arena, segment_id, ptr.offset(i) as *mut _));
                         }
                         ptr::write_bytes(ptr, 0u8, count);
                     }
                     InlineComposite => {
                         let element_tag: *mut WirePointer = ptr as *mut _;
 
-                        assert!((*element_tag).kind() == WirePointerKind::Struct,
-                                "Don't know how to handle non-STRUCT inline composite");
+                        if (*element_tag).kind() != WirePointerKind::Struct {
+                            return Err(Error::with_reason(ErrorReason::MalformedList,
+                                "Don't know how to handle non-STRUCT inline composite".to_string()));
+                        }
 
                         let data_size = (*element_tag).struct_ref().data_size.get();
                         let pointer_count = (*element_tag).struct_ref().ptr_count.get();
@@ -613,18 +693,22 @@ mod wire_helpers {
                             for _ in 0..count {
                                 pos = pos.offset(data_size as isize);
                                 for _ in 0..pointer_count {
-                                    zero_object(arena, segment_id, pos as *mut WirePointer);
+                                    try!(zero_object(arena, segment_id, pos as *mut WirePointer));
                                     pos = pos.offset(1);
                                 }
                             }
                         }
                         ptr::write_bytes(ptr, 0u8,
-                                         ((*element_tag).struct_ref().word_size() * count + 1) as usize);
+                                         ((*element_tag).struct_ref().word_size() as u64 * count as u64
+                                          + 1) as usize);
                     }
                 }
             }
-            WirePointerKind::Far => { panic!("Unexpected FAR pointer") }
+            WirePointerKind::Far => {
+                return Err(Error::with_reason(ErrorReason::MalformedFarPointer, "Unexpected FAR pointer".to_string()));
+            }
         }
+        Ok(())
     }
 
     #[inline]
@@ -651,14 +735,15 @@ mod wire_helpers {
         arena: &ReaderArena,
         segment_id: u32,
         reff: *const WirePointer,
-        mut nesting_limit: i32) -> Result<MessageSize>
+        mut nesting_limit: i32,
+        path: &mut TraversalPath) -> Result<MessageSize>
     {
         let mut result = MessageSize { word_count: 0, cap_count: 0};
 
         if (*reff).is_null() { return Ok(result) };
 
         if nesting_limit <= 0 {
-            return Err(Error::failed("Message is too deeply nested.".to_string()));
+            return Err(Error::with_reason(ErrorReason::NestingLimitExceeded, "Message is too deeply nested.".to_string()));
         }
 
         nesting_limit -= 1;
@@ -676,7 +761,9 @@ mod wire_helpers {
                     ptr.offset((*reff).struct_ref().data_size.get() as isize) as *const _;
                 let count: isize = (*reff).struct_ref().ptr_count.get() as isize;
                 for i in 0..count {
-                    result.plus_eq(try!(total_size(arena, segment_id, pointer_section.offset(i), nesting_limit)));
+                    let pointer = pointer_section.offset(i);
+                    result.plus_eq(try!(total_size(arena, segment_id, pointer, nesting_limit, path)
+                        .map_err(|e| path.record(PathStep::Pointer(i as u32), segment_id, pointer, e))));
                 }
             }
             WirePointerKind::List => {
@@ -699,10 +786,10 @@ mod wire_helpers {
                         result.word_count += count as u64 * WORDS_PER_POINTER as u64;
 
                         for i in 0..count as isize {
-                            result.plus_eq(
-                                try!(total_size(arena, segment_id,
-                                                (ptr as *const WirePointer).offset(i),
-                                                nesting_limit)));
+                            let element = (ptr as *const WirePointer).offset(i);
+                            result.plus_eq(try!(total_size(arena, segment_id, element, nesting_limit, path)
+                                .map_err(|e| path.record(PathStep::ListElement(i as u32), segment_id,
+                                                         element, e))));
                         }
                     }
                     InlineComposite => {
@@ -719,13 +806,13 @@ mod wire_helpers {
                         let count = (*element_tag).inline_composite_list_element_count();
 
                         if (*element_tag).kind() != WirePointerKind::Struct {
-                            return Err(Error::failed(
+                            return Err(Error::with_reason(ErrorReason::MalformedList,
                                 "Don't know how to handle non-STRUCT inline composite.".to_string()));
                         }
 
                         let actual_size = (*element_tag).struct_ref().word_size() as u64 * count as u64;
                         if actual_size > word_count as u64 {
-                            return Err(Error::failed(
+                            return Err(Error::with_reason(ErrorReason::MalformedList,
                                 "InlineComposite list's elements overrun its word count.".to_string()));
                         }
 
@@ -738,13 +825,15 @@ mod wire_helpers {
 
                         if pointer_count > 0 {
                             let mut pos: *const Word = ptr.offset(POINTER_SIZE_IN_WORDS as isize);
-                            for _ in 0..count {
+                            for i in 0..count {
                                 pos = pos.offset(data_size as isize);
+                                let element = pos as *const WirePointer;
 
-                                for _ in 0..pointer_count {
-                                    result.plus_eq(
-                                        try!(total_size(arena, segment_id,
-                                                        pos as *const WirePointer, nesting_limit)));
+                                for j in 0..pointer_count {
+                                    let pointer = pos as *const WirePointer;
+                                    result.plus_eq(try!(total_size(arena, segment_id, pointer, nesting_limit, path)
+                                        .map_err(|e| path.record(PathStep::Pointer(j as u32), segment_id, pointer, e))
+                                        .map_err(|e| path.record(PathStep::ListElement(i), segment_id, element, e))));
                                     pos = pos.offset(POINTER_SIZE_IN_WORDS as isize);
                                 }
                             }
@@ -753,13 +842,13 @@ mod wire_helpers {
                 }
             }
             WirePointerKind::Far => {
-                return Err(Error::failed("Malformed double-far pointer.".to_string()));
+                return Err(Error::with_reason(ErrorReason::MalformedFarPointer, "Malformed double-far pointer.".to_string()));
             }
             WirePointerKind::Other => {
                 if (*reff).is_capability() {
                     result.cap_count += 1;
                 } else {
-                    return Err(Error::failed("Unknown pointer type.".to_string()));
+                    return Err(Error::with_reason(ErrorReason::UnknownPointerType, "Unknown pointer type.".to_string()));
                 }
             }
         }
@@ -767,10 +856,132 @@ mod wire_helpers {
         Ok(result)
     }
 
+    /// Checks that `reff` and everything reachable from it can be read without error, charging
+    /// the read limiter just as a normal traversal would. Every list element is checked with
+    /// the nesting limit that applies when the list is read as a struct list, since that is the
+    /// deepest any reader can see it.
+    pub unsafe fn validate(
+        arena: &ReaderArena,
+        segment_id: u32,
+        reff: *const WirePointer,
+        nesting_limit: i32,
+        path: &mut TraversalPath) -> Result<()>
+    {
+        if (*reff).is_null() { return Ok(()) }
+
+        if (*reff).kind() == WirePointerKind::Other {
+            if (*reff).is_capability() {
+                return Ok(())
+            } else {
+                return Err(Error::with_reason(ErrorReason::UnknownPointerType, "Unknown pointer type.".to_string()));
+            }
+        }
+
+        if nesting_limit <= 0 {
+            return Err(Error::with_reason(ErrorReason::NestingLimitExceeded, "Message is too deeply nested.".to_string()));
+        }
+
+        let (ptr, reff, segment_id) = try!(follow_fars(arena, reff, (*reff).target(), segment_id));
+
+        match (*reff).kind() {
+            WirePointerKind::Struct => {
+                try!(bounds_check(arena, segment_id,
+                                  ptr, ptr.offset((*reff).struct_ref().word_size() as isize),
+                                  WirePointerKind::Struct));
+
+                let pointer_section: *const WirePointer =
+                    ptr.offset((*reff).struct_ref().data_size.get() as isize) as *const _;
+                let count: isize = (*reff).struct_ref().ptr_count.get() as isize;
+                for i in 0..count {
+                    let pointer = pointer_section.offset(i);
+                    try!(validate(arena, segment_id, pointer, nesting_limit - 1, path)
+                        .map_err(|e| path.record(PathStep::Pointer(i as u32), segment_id, pointer, e)));
+                }
+            }
+            WirePointerKind::List => {
+                let element_size = (*reff).list_ref().element_size();
+                match element_size {
+                    InlineComposite => {
+                        let word_count = (*reff).list_ref().inline_composite_word_count();
+                        try!(bounds_check(arena, segment_id, ptr,
+                                          ptr.offset(word_count as isize + POINTER_SIZE_IN_WORDS as isize),
+                                          WirePointerKind::List));
+
+                        let element_tag: *const WirePointer = ptr as *const _;
+                        if (*element_tag).kind() != WirePointerKind::Struct {
+                            return Err(Error::with_reason(ErrorReason::MalformedList,
+                                "InlineComposite lists of non-STRUCT type are not supported.".to_string()));
+                        }
+
+                        let count = (*element_tag).inline_composite_list_element_count();
+                        let words_per_element = (*element_tag).struct_ref().word_size();
+                        if count as u64 * words_per_element as u64 > word_count as u64 {
+                            return Err(Error::with_reason(ErrorReason::MalformedList,
+                                "InlineComposite list's elements overrun its word count.".to_string()));
+                        }
+
+                        if words_per_element == 0 {
+                            try!(amplified_read(arena, count as u64));
+                        }
+
+                        let data_size = (*element_tag).struct_ref().data_size.get();
+                        let pointer_count = (*element_tag).struct_ref().ptr_count.get();
+
+                        if pointer_count > 0 {
+                            let mut pos: *const Word = ptr.add(POINTER_SIZE_IN_WORDS);
+                            for i in 0..count {
+                                pos = pos.offset(data_size as isize);
+                                let element = pos as *const WirePointer;
+
+                                for j in 0..pointer_count {
+                                    let pointer = pos as *const WirePointer;
+                                    try!(validate(arena, segment_id, pointer, nesting_limit - 2, path)
+                                        .map_err(|e| path.record(PathStep::Pointer(j as u32), segment_id, pointer, e))
+                                        .map_err(|e| path.record(PathStep::ListElement(i), segment_id, element, e)));
+                                    pos = pos.add(POINTER_SIZE_IN_WORDS);
+                                }
+                            }
+                        }
+                    }
+                    _ => {
+                        let element_count = (*reff).list_ref().element_count();
+                        let step = data_bits_per_element(element_size) +
+                            pointers_per_element(element_size) * BITS_PER_POINTER as u32;
+                        let word_count = round_bits_up_to_words(element_count as u64 * step as u64);
+                        try!(bounds_check(
+                            arena, segment_id, ptr, ptr.offset(word_count as isize), WirePointerKind::List));
+
+                        if element_size == Void {
+                            try!(amplified_read(arena, element_count as u64));
+                        }
+
+                        if element_size == Pointer {
+                            for i in 0..element_count as isize {
+                                let element = (ptr as *const WirePointer).offset(i);
+                                try!(validate(arena, segment_id, element, nesting_limit - 2, path)
+                                    .map_err(|e| path.record(PathStep::ListElement(i as u32), segment_id,
+                                                             element, e)));
+                            }
+                        }
+                    }
+                }
+            }
+            WirePointerKind::Far => {
+                return Err(Error::with_reason(ErrorReason::MalformedFarPointer, "Malformed double-far pointer.".to_string()));
+            }
+            WirePointerKind::Other => {
+                return Err(Error::with_reason(ErrorReason::MalformedFarPointer,
+                    "Far pointer lands on a non-struct, non-list pointer.".to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
     pub unsafe fn transfer_pointer(
         arena: &BuilderArena,
         dst_segment_id: u32, dst: *mut WirePointer,
-        src_segment_id: u32, src: *mut WirePointer)
+        src_segment_id: u32, src: *mut WirePointer) -> Result<()>
     {
         //# Make *dst point to the same object as *src. Both must
         //# reside in the same message, but can be in different
@@ -782,23 +993,27 @@ mod wire_helpers {
         //# do this zeroing itself because many callers transfer
         //# several pointers in a loop then zero out the whole section.
 
-        assert!((*dst).is_null());
         // We expect the caller to ensure the target is already null so won't leak.
+        if !(*dst).is_null() {
+            return Err(Error::failed(
+                "Cannot transfer a pointer onto a location that still owns an object.".to_string()));
+        }
 
         if (*src).is_null() {
             ptr::write_bytes(dst, 0, 1);
         } else if (*src).is_positional() {
-            transfer_pointer_split(arena, dst_segment_id, dst, src_segment_id, src, (*src).mut_target());
+            try!(transfer_pointer_split(arena, dst_segment_id, dst, src_segment_id, src, (*src).mut_target()));
         } else {
             ptr::copy_nonoverlapping(src, dst, 1);
         }
+        Ok(())
     }
 
     pub unsafe fn transfer_pointer_split(
         arena: &BuilderArena,
         dst_segment_id: u32, dst: *mut WirePointer,
         src_segment_id: u32, src_tag: *mut WirePointer,
-        src_ptr: *mut Word)
+        src_ptr: *mut Word) -> Result<()>
     {
         // Like the other transfer_pointer, but splits src into a tag and a
         // target. Particularly useful for OrphanBuilder.
@@ -845,7 +1060,10 @@ mod wire_helpers {
                 Some(landing_pad_word) => {
                     //# Simple landing pad is just a pointer.
                     let (seg_start, seg_len) = arena.get_segment_mut(src_segment_id);
-                    assert!(landing_pad_word < seg_len);
+                    if landing_pad_word >= seg_len {
+                        return Err(Error::with_reason(ErrorReason::OutOfBoundsPointer,
+                            "Landing pad for a transferred pointer is outside its segment.".to_string()));
+                    }
                     let landing_pad: *mut WirePointer = seg_start.offset(landing_pad_word as isize) as *mut _;
                     (*landing_pad).set_kind_and_target((*src_tag).kind(), src_ptr);
                     ptr::copy_nonoverlapping(&(*src_tag).upper32bits,
@@ -856,6 +1074,7 @@ mod wire_helpers {
                 }
             }
         }
+        Ok(())
     }
 
     #[inline]
@@ -864,17 +1083,17 @@ mod wire_helpers {
         reff: *mut WirePointer,
         segment_id: u32,
         cap_table: CapTableBuilder,
-        size: StructSize) -> StructBuilder<'a>
+        size: StructSize) -> Result<StructBuilder<'a>>
     {
-        let (ptr, reff, segment_id) = allocate(
+        let (ptr, reff, segment_id) = try!(allocate(
             arena,
             reff,
             segment_id,
             size.total(),
-            WirePointerKind::Struct);
+            WirePointerKind::Struct));
         (*reff).mut_struct_ref().set_from_struct_size(size);
 
-        StructBuilder {
+        Ok(StructBuilder {
             arena: arena,
             segment_id: segment_id,
             cap_table: cap_table,
@@ -882,7 +1101,7 @@ mod wire_helpers {
             pointers: ptr.offset((size.data as usize) as isize) as *mut _,
             data_size: size.data as WordCount32 * (BITS_PER_WORD as BitCount32),
             pointer_count: size.pointers,
-        }
+        })
     }
 
     #[inline]
@@ -898,14 +1117,14 @@ mod wire_helpers {
 
         if (*reff).is_null() {
             if default_value.is_null() || (*(default_value as *const WirePointer)).is_null() {
-                return Ok(init_struct_pointer(arena, reff, segment_id, cap_table, size));
+                return init_struct_pointer(arena, reff, segment_id, cap_table, size);
             }
             unimplemented!()
         }
 
         let (old_ptr, old_ref, old_segment_id) = try!(follow_builder_fars(arena, reff, ref_target, segment_id));
         if (*old_ref).kind() != WirePointerKind::Struct {
-            return Err(Error::failed(
+            return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                 "Message contains non-struct pointer where struct pointer was expected.".to_string()));
         }
 
@@ -927,7 +1146,7 @@ mod wire_helpers {
             //# Don't let allocate() zero out the object just yet.
             try!(zero_pointer_and_fars(arena, segment_id, reff));
 
-            let (ptr, reff, segment_id) = allocate(arena, reff, segment_id, total_size, WirePointerKind::Struct);
+            let (ptr, reff, segment_id) = try!(allocate(arena, reff, segment_id, total_size, WirePointerKind::Struct));
             (*reff).mut_struct_ref().set(new_data_size, new_pointer_count);
 
             // Copy data section.
@@ -937,8 +1156,8 @@ mod wire_helpers {
             //# Copy pointer section.
             let new_pointer_section: *mut WirePointer = ptr.offset(new_data_size as isize) as *mut _;
             for i in 0..old_pointer_count as isize {
-                transfer_pointer(arena, segment_id, new_pointer_section.offset(i),
-                                 old_segment_id, old_pointer_section.offset(i));
+                try!(transfer_pointer(arena, segment_id, new_pointer_section.offset(i),
+                                 old_segment_id, old_pointer_section.offset(i)));
             }
 
             ptr::write_bytes(old_ptr, 0, old_data_size as usize + old_pointer_count as usize);
@@ -972,7 +1191,7 @@ mod wire_helpers {
         segment_id: u32,
         cap_table: CapTableBuilder,
         element_count: ElementCount32,
-        element_size: ElementSize) -> ListBuilder<'a>
+        element_size: ElementSize) -> Result<ListBuilder<'a>>
     {
         assert!(element_size != InlineComposite,
                 "Should have called initStructListPointer() instead");
@@ -981,20 +1200,24 @@ mod wire_helpers {
         let pointer_count = pointers_per_element(element_size);
         let step = data_size + pointer_count * BITS_PER_POINTER as u32;
         let word_count = round_bits_up_to_words(element_count as ElementCount64 * (step as u64));
-        let (ptr, reff, segment_id) = allocate(arena, reff, segment_id, word_count, WirePointerKind::List);
+        let (origin, origin_segment_id) = (reff, segment_id);
+        let (ptr, reff, segment_id) = try!(allocate(arena, reff, segment_id, word_count, WirePointerKind::List));
 
         (*reff).mut_list_ref().set(element_size, element_count);
 
-        ListBuilder {
+        Ok(ListBuilder {
             arena: arena,
             segment_id: segment_id,
             cap_table: cap_table,
             ptr: ptr as *mut _,
             step: step,
             element_count: element_count,
+            element_size: element_size,
             struct_data_size: data_size,
-            struct_pointer_count: pointer_count as u16
-        }
+            struct_pointer_count: pointer_count as u16,
+            origin: origin,
+            origin_segment_id: origin_segment_id,
+        })
     }
 
     #[inline]
@@ -1004,17 +1227,18 @@ mod wire_helpers {
         segment_id: u32,
         cap_table: CapTableBuilder,
         element_count: ElementCount32,
-        element_size: StructSize) -> ListBuilder<'a>
+        element_size: StructSize) -> Result<ListBuilder<'a>>
     {
         let words_per_element = element_size.total();
 
         //# Allocate the list, prefixed by a single WirePointer.
         let word_count: WordCount32 = element_count * words_per_element;
-        let (ptr, reff, segment_id) = allocate(arena,
+        let (origin, origin_segment_id) = (reff, segment_id);
+        let (ptr, reff, segment_id) = try!(allocate(arena,
                                                reff,
                                                segment_id,
                                                POINTER_SIZE_IN_WORDS as u32 + word_count,
-                                               WirePointerKind::List);
+                                               WirePointerKind::List));
         let ptr = ptr as *mut WirePointer;
 
         //# Initialize the pointer.
@@ -1024,16 +1248,19 @@ mod wire_helpers {
 
         let ptr1 = ptr.offset(POINTER_SIZE_IN_WORDS as isize);
 
-        ListBuilder {
+        Ok(ListBuilder {
             arena: arena,
             segment_id: segment_id,
             cap_table: cap_table,
             ptr: ptr1 as *mut _,
             step: words_per_element * BITS_PER_WORD as u32,
             element_count: element_count,
+            element_size: InlineComposite,
             struct_data_size: element_size.data as u32 * (BITS_PER_WORD as u32),
-            struct_pointer_count: element_size.pointers
-        }
+            struct_pointer_count: element_size.pointers,
+            origin: origin,
+            origin_segment_id: origin_segment_id,
+        })
     }
 
     #[inline]
@@ -1052,8 +1279,23 @@ mod wire_helpers {
 
         if (*orig_ref).is_null() {
             if default_value.is_null() || (*(default_value as *const WirePointer)).is_null() {
-                    return Ok(ListBuilder::new_default());
-                }
+                // An empty list that still knows where it lives, so that it can be resized.
+                let data_size = data_bits_per_element(element_size);
+                let pointer_count = pointers_per_element(element_size);
+                return Ok(ListBuilder {
+                    arena: arena,
+                    segment_id: orig_segment_id,
+                    cap_table: cap_table,
+                    ptr: ptr::null_mut(),
+                    element_count: 0,
+                    element_size: element_size,
+                    step: data_size + pointer_count * BITS_PER_POINTER as u32,
+                    struct_data_size: data_size,
+                    struct_pointer_count: pointer_count as u16,
+                    origin: orig_ref,
+                    origin_segment_id: orig_segment_id,
+                });
+            }
             unimplemented!()
         }
 
@@ -1066,7 +1308,7 @@ mod wire_helpers {
             try!(follow_builder_fars(arena, orig_ref, orig_ref_target, orig_segment_id));
 
         if (*reff).kind() != WirePointerKind::List {
-            return Err(Error::failed(
+            return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                 "Called get_list_{{field,element}}() but existing pointer is not a list.".to_string()));
         }
 
@@ -1083,7 +1325,7 @@ mod wire_helpers {
             let tag: *const WirePointer = ptr as *const _;
 
             if (*tag).kind() != WirePointerKind::Struct {
-                return Err(Error::failed(
+                return Err(Error::with_reason(ErrorReason::MalformedList,
                     "InlineComposite list with non-STRUCT elements not supported.".to_string()));
             }
 
@@ -1095,18 +1337,18 @@ mod wire_helpers {
             match element_size {
                 Void => {} // Anything is a valid upgrade from Void.
                 Bit => {
-                    return Err(Error::failed(
+                    return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                         "Found struct list where bit list was expected.".to_string()));
                 }
                 Byte | TwoBytes | FourBytes | EightBytes => {
                     if data_size < 1 {
-                        return Err(Error::failed(
+                        return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                             "Existing list value is incompatible with expected type.".to_string()));
                     }
                 }
                 Pointer => {
                     if pointer_count < 1 {
-                        return Err(Error::failed(
+                        return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                             "Existing list value is incompatible with expected type.".to_string()));
                     }
                     // Adjust the pointer to point at the reference segment.
@@ -1124,9 +1366,12 @@ mod wire_helpers {
                 cap_table: cap_table,
                 ptr: ptr as *mut _,
                 element_count: (*tag).inline_composite_list_element_count(),
+                element_size: InlineComposite,
                 step: (*tag).struct_ref().word_size() * BITS_PER_WORD as u32,
                 struct_data_size: data_size as u32 * BITS_PER_WORD as u32,
-                struct_pointer_count: pointer_count
+                struct_pointer_count: pointer_count,
+                origin: orig_ref,
+                origin_segment_id: orig_segment_id,
             })
         } else {
             let data_size = data_bits_per_element(old_size);
@@ -1134,7 +1379,7 @@ mod wire_helpers {
 
             if data_size < data_bits_per_element(element_size) ||
                 pointer_count < pointers_per_element(element_size) {
-                return Err(Error::failed(
+                return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                     "Existing list value is incompatible with expected type.".to_string()));
             }
 
@@ -1147,8 +1392,11 @@ mod wire_helpers {
                 ptr: ptr as *mut _,
                 step: step,
                 element_count: (*reff).list_ref().element_count(),
+                element_size: old_size,
                 struct_data_size: data_size,
-                struct_pointer_count: pointer_count as u16
+                struct_pointer_count: pointer_count as u16,
+                origin: orig_ref,
+                origin_segment_id: orig_segment_id,
             })
         }
     }
@@ -1166,7 +1414,20 @@ mod wire_helpers {
 
         if (*orig_ref).is_null() {
             if default_value.is_null() || (*(default_value as *const WirePointer)).is_null() {
-                return Ok(ListBuilder::new_default());
+                // An empty list that still knows where it lives, so that it can be resized.
+                return Ok(ListBuilder {
+                    arena: arena,
+                    segment_id: orig_segment_id,
+                    cap_table: cap_table,
+                    ptr: ptr::null_mut(),
+                    element_count: 0,
+                    element_size: InlineComposite,
+                    step: element_size.total() * BITS_PER_WORD as u32,
+                    struct_data_size: element_size.data as u32 * BITS_PER_WORD as u32,
+                    struct_pointer_count: element_size.pointers,
+                    origin: orig_ref,
+                    origin_segment_id: orig_segment_id,
+                });
             }
             unimplemented!()
         }
@@ -1177,7 +1438,7 @@ mod wire_helpers {
             try!(follow_builder_fars(arena, orig_ref, orig_ref_target, orig_segment_id));
 
         if (*old_ref).kind() != WirePointerKind::List {
-            return Err(Error::failed(
+            return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                 "Called getList{{Field,Element}} but existing pointer is not a list.".to_string()));
         }
 
@@ -1189,7 +1450,7 @@ mod wire_helpers {
             let old_tag: *const WirePointer = old_ptr as *const _;
             old_ptr = old_ptr.offset(POINTER_SIZE_IN_WORDS as isize);
             if (*old_tag).kind() != WirePointerKind::Struct {
-                return Err(Error::failed(
+                return Err(Error::with_reason(ErrorReason::MalformedList,
                     "InlineComposite list with non-STRUCT elements not supported.".to_string()));
             }
 
@@ -1206,9 +1467,12 @@ mod wire_helpers {
                     cap_table: cap_table,
                     ptr: old_ptr as *mut _,
                     element_count: element_count,
+                    element_size: InlineComposite,
                     step: old_step * BITS_PER_WORD as u32,
                     struct_data_size: old_data_size as u32 * BITS_PER_WORD as u32,
-                    struct_pointer_count: old_pointer_count
+                    struct_pointer_count: old_pointer_count,
+                    origin: orig_ref,
+                    origin_segment_id: orig_segment_id,
                 });
             }
 
@@ -1224,8 +1488,8 @@ mod wire_helpers {
             try!(zero_pointer_and_fars(arena, orig_segment_id, orig_ref));
 
             let (mut new_ptr, new_ref, new_segment_id) =
-                allocate(arena, orig_ref, orig_segment_id,
-                         total_size + POINTER_SIZE_IN_WORDS as u32, WirePointerKind::List);
+                try!(allocate(arena, orig_ref, orig_segment_id,
+                         total_size + POINTER_SIZE_IN_WORDS as u32, WirePointerKind::List));
             (*new_ref).mut_list_ref().set_inline_composite(total_size);
 
             let new_tag: *mut WirePointer = new_ptr as *mut _;
@@ -1243,9 +1507,9 @@ mod wire_helpers {
                 let new_pointer_section: *mut WirePointer = dst.offset(new_data_size as isize) as *mut _;
                 let old_pointer_section: *mut WirePointer = src.offset(old_data_size as isize) as *mut _;
                 for jj in 0..(old_pointer_count as isize) {
-                    transfer_pointer(arena, new_segment_id,
+                    try!(transfer_pointer(arena, new_segment_id,
                                      new_pointer_section.offset(jj),
-                                     old_segment_id, old_pointer_section.offset(jj));
+                                     old_segment_id, old_pointer_section.offset(jj)));
                 }
 
                 dst = dst.offset(new_step as isize);
@@ -1261,9 +1525,12 @@ mod wire_helpers {
                 cap_table: cap_table,
                 ptr: new_ptr as *mut _,
                 element_count: element_count,
+                element_size: InlineComposite,
                 step: new_step * BITS_PER_WORD as u32,
                 struct_data_size: new_data_size as u32 * BITS_PER_WORD as u32,
                 struct_pointer_count: new_pointer_count,
+                origin: orig_ref,
+                origin_segment_id: orig_segment_id,
             })
         } else {
             // We're upgrading from a non-struct list.
@@ -1275,13 +1542,13 @@ mod wire_helpers {
 
             if old_size == ElementSize::Void {
                 // Nothing to copy, just allocate a new list.
-                return Ok(init_struct_list_pointer(
-                    arena, orig_ref, orig_segment_id, cap_table, element_count, element_size));
+                return init_struct_list_pointer(
+                    arena, orig_ref, orig_segment_id, cap_table, element_count, element_size);
             } else {
                 // Upgrade to an inline composite list.
 
                 if old_size == ElementSize::Bit {
-                    return Err(Error::failed(
+                    return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                         "Found bit list where struct list was expected; upgrading boolean \
                          lists to struct lists is no longer supported.".to_string()));
                 }
@@ -1303,8 +1570,8 @@ mod wire_helpers {
                 try!(zero_pointer_and_fars(arena, orig_segment_id, orig_ref));
 
                 let (mut new_ptr, new_ref, new_segment_id) =
-                    allocate(arena, orig_ref, orig_segment_id,
-                             total_words + POINTER_SIZE_IN_WORDS as u32, WirePointerKind::List);
+                    try!(allocate(arena, orig_ref, orig_segment_id,
+                             total_words + POINTER_SIZE_IN_WORDS as u32, WirePointerKind::List));
                 (*new_ref).mut_list_ref().set_inline_composite(total_words);
 
                 let tag: *mut WirePointer = new_ptr as *mut _;
@@ -1316,7 +1583,7 @@ mod wire_helpers {
                     let mut dst: *mut Word = new_ptr.offset(new_data_size as isize);
                     let mut src: *mut WirePointer = old_ptr as *mut _;
                     for _ in 0..element_count {
-                        transfer_pointer(arena, new_segment_id, dst as *mut _, old_segment_id, src);
+                        try!(transfer_pointer(arena, new_segment_id, dst as *mut _, old_segment_id, src));
                         dst = dst.offset(new_step as isize / WORDS_PER_POINTER as isize);
                         src = src.offset(1);
                     }
@@ -1341,36 +1608,250 @@ mod wire_helpers {
                     cap_table: cap_table,
                     ptr: new_ptr as *mut _,
                     element_count: element_count,
+                    element_size: InlineComposite,
                     step: new_step * BITS_PER_WORD as u32,
                     struct_data_size: new_data_size as u32 * BITS_PER_WORD as u32,
-                    struct_pointer_count: new_pointer_count
+                    struct_pointer_count: new_pointer_count,
+                    origin: orig_ref,
+                    origin_segment_id: orig_segment_id,
                 })
             }
         }
     }
 
+    fn stale_list_builder() -> Error {
+        Error::failed("List builder is out of date; the list has been moved or replaced.".to_string())
+    }
+
+    /// A list's element layout: (element count, step in bits, data bits per element, pointers
+    /// per element).
+    type ListLayout = (ElementCount32, BitCount32, BitCount32, u16);
+
+    /// Locates the list that `list.origin` currently refers to, returning the start of its
+    /// elements, the pointer holding its list reference, its segment, and its element layout.
+    /// Errors if `list` no longer views that list.
+    unsafe fn locate_list(list: &ListBuilder)
+        -> Result<(*mut Word, *mut WirePointer, u32, ListLayout)>
+    {
+        let (target, reff, segment_id) =
+            try!(follow_builder_fars(list.arena, list.origin, (*list.origin).mut_target(),
+                                     list.origin_segment_id));
+        if (*reff).kind() != WirePointerKind::List {
+            return Err(stale_list_builder());
+        }
+
+        if (*reff).list_ref().element_size() == InlineComposite {
+            let tag: *const WirePointer = target as *const _;
+            let data_words = (*tag).struct_ref().data_size.get();
+            let pointer_count = (*tag).struct_ref().ptr_count.get();
+            let step = (*tag).struct_ref().word_size() * BITS_PER_WORD as u32;
+            let start = target.add(POINTER_SIZE_IN_WORDS);
+
+            // An upgraded view of a struct list may start partway into the first element.
+            let view_offset = list.ptr as usize;
+            if view_offset < start as usize ||
+                view_offset - start as usize >= ::std::cmp::max(step as usize / BITS_PER_BYTE, 1) {
+                return Err(stale_list_builder());
+            }
+            Ok((start, reff, segment_id,
+                ((*tag).inline_composite_list_element_count(), step,
+                 data_words as u32 * BITS_PER_WORD as u32, pointer_count)))
+        } else {
+            let element_size = (*reff).list_ref().element_size();
+            if list.ptr != target as *mut u8 {
+                return Err(stale_list_builder());
+            }
+            let data_size = data_bits_per_element(element_size);
+            let pointer_count = pointers_per_element(element_size);
+            Ok((target, reff, segment_id,
+                ((*reff).list_ref().element_count(),
+                 data_size + pointer_count * BITS_PER_POINTER as u32,
+                 data_size, pointer_count as u16)))
+        }
+    }
+
+    pub unsafe fn truncate_list(list: &mut ListBuilder, new_len: ElementCount32) -> Result<()> {
+        if new_len > list.element_count {
+            return Err(Error::with_reason(ErrorReason::IndexOutOfRange,
+                format!("Cannot truncate a list of {} elements to {} elements.",
+                        list.element_count, new_len)));
+        }
+        if new_len == list.element_count {
+            return Ok(());
+        }
+        if list.origin.is_null() || (*list.origin).is_null() {
+            return Err(stale_list_builder());
+        }
+
+        let (start, reff, segment_id, (old_len, step, data_size, pointer_count)) =
+            try!(locate_list(list));
+        if new_len > old_len {
+            return Err(stale_list_builder());
+        }
+        let start = start as *mut u8;
+
+        // Zero whatever the removed elements own.
+        for i in new_len..old_len {
+            let element = start.offset((i as u64 * step as u64 / BITS_PER_BYTE as u64) as isize);
+            let pointers: *mut WirePointer =
+                element.add(data_size as usize / BITS_PER_BYTE) as *mut _;
+            for j in 0..(pointer_count as isize) {
+                try!(zero_object(list.arena, segment_id, pointers.offset(j)));
+            }
+        }
+
+        // Zero the removed elements themselves, bit by bit up to the first byte boundary.
+        let end_bit = old_len as u64 * step as u64;
+        let mut bit = new_len as u64 * step as u64;
+        while bit & (BITS_PER_BYTE as u64 - 1) != 0 && bit < end_bit {
+            *start.offset((bit / BITS_PER_BYTE as u64) as isize) &= !(1u8 << (bit % BITS_PER_BYTE as u64));
+            bit += 1;
+        }
+        if bit < end_bit {
+            ptr::write_bytes(start.offset((bit / BITS_PER_BYTE as u64) as isize), 0,
+                             round_bits_up_to_bytes(end_bit - bit) as usize);
+        }
+
+        if (*reff).list_ref().element_size() == InlineComposite {
+            let tag: *mut WirePointer = (start as *mut WirePointer).offset(-1);
+            (*reff).mut_list_ref().set_inline_composite(new_len * (step / BITS_PER_WORD as u32));
+            (*tag).set_kind_and_inline_composite_list_element_count(WirePointerKind::Struct, new_len);
+        } else {
+            let element_size = (*reff).list_ref().element_size();
+            (*reff).mut_list_ref().set(element_size, new_len);
+        }
+
+        list.element_count = new_len;
+        Ok(())
+    }
+
+    pub unsafe fn resize_list(list: &mut ListBuilder, new_len: ElementCount32) -> Result<()> {
+        if new_len <= list.element_count {
+            return truncate_list(list, new_len);
+        }
+        if list.origin.is_null() {
+            return Err(Error::failed("Cannot resize a list that is not part of a message.".to_string()));
+        }
+
+        let arena = list.arena;
+        let origin = list.origin;
+        let origin_segment_id = list.origin_segment_id;
+
+        if (*origin).is_null() {
+            if list.element_count != 0 {
+                return Err(stale_list_builder());
+            }
+            let new_list = if list.element_size == InlineComposite {
+                let size = StructSize {
+                    data: (list.struct_data_size / BITS_PER_WORD as u32) as u16,
+                    pointers: list.struct_pointer_count,
+                };
+                try!(init_struct_list_pointer(arena, origin, origin_segment_id, list.cap_table, new_len, size))
+            } else {
+                try!(init_list_pointer(arena, origin, origin_segment_id, list.cap_table, new_len,
+                                       list.element_size))
+            };
+            list.segment_id = new_list.segment_id;
+            list.ptr = new_list.ptr;
+            list.element_count = new_len;
+            return Ok(());
+        }
+
+        let (old_start, old_ref, old_segment_id, (old_len, step, data_size, pointer_count)) =
+            try!(locate_list(list));
+        let old_element_size = (*old_ref).list_ref().element_size();
+        let view_offset = list.ptr as usize - old_start as usize;
+
+        // If the list is the last thing allocated in its segment, it can grow in place.
+        let old_words = round_bits_up_to_words(old_len as u64 * step as u64);
+        let new_words = round_bits_up_to_words(new_len as u64 * step as u64);
+        let (seg_start, _seg_len) = arena.get_segment_mut(old_segment_id);
+        let end = ((old_start as usize - seg_start as usize) / BYTES_PER_WORD) as u32 + old_words;
+        if new_words == old_words || arena.try_extend(old_segment_id, end, new_words - old_words) {
+            if old_element_size == InlineComposite {
+                let tag: *mut WirePointer = (old_start as *mut WirePointer).offset(-1);
+                (*old_ref).mut_list_ref().set_inline_composite(new_words);
+                (*tag).set_kind_and_inline_composite_list_element_count(WirePointerKind::Struct, new_len);
+            } else {
+                (*old_ref).mut_list_ref().set(old_element_size, new_len);
+            }
+            list.element_count = new_len;
+            return Ok(());
+        }
+
+        // Move the list out of the way so that allocating the new one does not zero it.
+        let mut old_pointer: WirePointer = ptr::read(origin);
+        ptr::write_bytes(origin, 0, 1);
+
+        let new_list = if old_element_size == InlineComposite {
+            let size = StructSize {
+                data: (data_size / BITS_PER_WORD as u32) as u16,
+                pointers: pointer_count,
+            };
+            try!(init_struct_list_pointer(arena, origin, origin_segment_id, list.cap_table, new_len, size))
+        } else {
+            try!(init_list_pointer(arena, origin, origin_segment_id, list.cap_table, new_len,
+                                   old_element_size))
+        };
+        let new_start = new_list.ptr;
+
+        if pointer_count == 0 {
+            ptr::copy_nonoverlapping(old_start as *const u8, new_start,
+                                     round_bits_up_to_bytes(old_len as u64 * step as u64) as usize);
+        } else {
+            let byte_step = (step as usize / BITS_PER_BYTE) as isize;
+            let data_bytes = data_size as usize / BITS_PER_BYTE;
+            for i in 0..(old_len as isize) {
+                let src = (old_start as *mut u8).offset(i * byte_step);
+                let dst = new_start.offset(i * byte_step);
+                ptr::copy_nonoverlapping(src, dst, data_bytes);
+                let src_pointers: *mut WirePointer = src.add(data_bytes) as *mut _;
+                let dst_pointers: *mut WirePointer = dst.add(data_bytes) as *mut _;
+                for j in 0..(pointer_count as isize) {
+                    try!(transfer_pointer(arena, new_list.segment_id, dst_pointers.offset(j),
+                                     old_segment_id, src_pointers.offset(j)));
+                }
+            }
+        }
+
+        // The old elements' contents now belong to the new list, so only the old list's own
+        // words (and any far pointer landing pads leading to them) are zeroed.
+        if old_element_size == InlineComposite {
+            ptr::write_bytes(old_start.sub(POINTER_SIZE_IN_WORDS), 0,
+                             POINTER_SIZE_IN_WORDS +
+                             round_bits_up_to_words(old_len as u64 * step as u64) as usize);
+        } else {
+            ptr::write_bytes(old_start, 0, round_bits_up_to_words(old_len as u64 * step as u64) as usize);
+        }
+        try!(zero_pointer_and_fars(arena, origin_segment_id, &mut old_pointer));
+
+        list.segment_id = new_list.segment_id;
+        list.ptr = new_start.add(view_offset);
+        list.element_count = new_len;
+        Ok(())
+    }
+
     #[inline]
     pub unsafe fn init_text_pointer<'a>(
         arena: &'a BuilderArena,
         reff: *mut WirePointer,
         segment_id: u32,
-        size: ByteCount32) -> SegmentAnd<text::Builder<'a>>
+        size: ByteCount32) -> Result<SegmentAnd<text::Builder<'a>>>
     {
         //# The byte list must include a NUL terminator.
         let byte_size = size + 1;
 
         //# Allocate the space.
         let (ptr, reff, segment_id) =
-            allocate(arena, reff, segment_id, round_bytes_up_to_words(byte_size), WirePointerKind::List);
+            try!(allocate(arena, reff, segment_id, round_bytes_up_to_words(byte_size), WirePointerKind::List));
 
         //# Initialize the pointer.
         (*reff).mut_list_ref().set(Byte, byte_size);
 
-        return SegmentAnd {
+        Ok(SegmentAnd {
             segment_id: segment_id,
-            value: text::Builder::new(slice::from_raw_parts_mut(ptr as *mut _, size as usize), 0)
-                .expect("empty text builder should be valid utf-8")
-        }
+            value: try!(text::Builder::new(slice::from_raw_parts_mut(ptr as *mut _, size as usize), 0)),
+        })
     }
 
     #[inline]
@@ -1378,13 +1859,13 @@ mod wire_helpers {
         arena: &'a BuilderArena,
         reff: *mut WirePointer,
         segment_id: u32,
-        value: &str) -> SegmentAnd<text::Builder<'a>>
+        value: &str) -> Result<SegmentAnd<text::Builder<'a>>>
     {
         let value_bytes = value.as_bytes();
         // TODO make sure the string is not longer than 2 ** 29.
-        let mut allocation = init_text_pointer(arena, reff, segment_id, value_bytes.len() as u32);
+        let mut allocation = try!(init_text_pointer(arena, reff, segment_id, value_bytes.len() as u32));
         allocation.value.push_str(value);
-        allocation
+        Ok(allocation)
     }
 
     #[inline]
@@ -1397,9 +1878,9 @@ mod wire_helpers {
     {
         if (*reff).is_null() {
             if default_size == 0 {
-                return text::Builder::new(slice::from_raw_parts_mut(ptr::null_mut(), 0), 0);
+                return text::Builder::new(&mut [], 0);
             } else {
-                let _builder = init_text_pointer(arena, reff, segment_id, default_size).value;
+                let _builder = try!(init_text_pointer(arena, reff, segment_id, default_size)).value;
                 unimplemented!()
             }
         }
@@ -1408,17 +1889,17 @@ mod wire_helpers {
         let cptr: *mut u8 = ptr as *mut _;
 
         if (*reff).kind() != WirePointerKind::List {
-            return Err(Error::failed(
+            return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                 "Called getText{{Field,Element}}() but existing pointer is not a list.".to_string()));
         }
         if (*reff).list_ref().element_size() != Byte {
-            return Err(Error::failed(
+            return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                 "Called getText{{Field,Element}}() but existing list pointer is not byte-sized.".to_string()));
         }
 
         let count = (*reff).list_ref().element_count();
         if count <= 0 || *cptr.offset((count - 1) as isize) != 0 {
-            return Err(Error::failed(
+            return Err(Error::with_reason(ErrorReason::MissingNulTerminator,
                 "Text blob missing NUL terminator.".to_string()));
         }
 
@@ -1431,16 +1912,16 @@ mod wire_helpers {
         arena: &'a BuilderArena,
         reff: *mut WirePointer,
         segment_id: u32,
-        size: ByteCount32) -> SegmentAnd<data::Builder<'a>>
+        size: ByteCount32) -> Result<SegmentAnd<data::Builder<'a>>>
     {
         //# Allocate the space.
         let (ptr, reff, segment_id) =
-            allocate(arena, reff, segment_id, round_bytes_up_to_words(size), WirePointerKind::List);
+            try!(allocate(arena, reff, segment_id, round_bytes_up_to_words(size), WirePointerKind::List));
 
         //# Initialize the pointer.
         (*reff).mut_list_ref().set(Byte, size);
 
-        SegmentAnd { segment_id: segment_id, value: data::new_builder(ptr as *mut _, size) }
+        Ok(SegmentAnd { segment_id: segment_id, value: data::new_builder(ptr as *mut _, size) })
     }
 
     #[inline]
@@ -1448,12 +1929,12 @@ mod wire_helpers {
         arena: &'a BuilderArena,
         reff: *mut WirePointer,
         segment_id: u32,
-        value: &[u8]) -> SegmentAnd<data::Builder<'a>>
+        value: &[u8]) -> Result<SegmentAnd<data::Builder<'a>>>
     {
-        let allocation = init_data_pointer(arena, reff, segment_id, value.len() as u32);
+        let allocation = try!(init_data_pointer(arena, reff, segment_id, value.len() as u32));
         ptr::copy_nonoverlapping(value.as_ptr(), allocation.value.as_mut_ptr(),
                                  value.len());
-        allocation
+        Ok(allocation)
     }
 
     #[inline]
@@ -1466,9 +1947,9 @@ mod wire_helpers {
     {
         if (*reff).is_null() {
             if default_size == 0 {
-                return Ok(data::new_builder(ptr::null_mut(), 0));
+                return Ok(&mut []);
             } else {
-                let builder = init_data_pointer(arena, reff, segment_id, default_size).value;
+                let builder = try!(init_data_pointer(arena, reff, segment_id, default_size)).value;
                 ptr::copy_nonoverlapping(default_value as *const _,
                                          builder.as_mut_ptr() as *mut _,
                                          default_size as usize);
@@ -1479,11 +1960,11 @@ mod wire_helpers {
         let (ptr, reff, _segment_id) = try!(follow_builder_fars(arena, reff, ref_target, segment_id));
 
         if (*reff).kind() != WirePointerKind::List {
-            return Err(Error::failed(
+            return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                 "Called getData{{Field,Element}}() but existing pointer is not a list.".to_string()));
         }
         if (*reff).list_ref().element_size() != Byte {
-            return Err(Error::failed(
+            return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                 "Called getData{{Field,Element}}() but existing list pointer is not byte-sized.".to_string()));
         }
 
@@ -1495,13 +1976,15 @@ mod wire_helpers {
         segment_id: u32,
         cap_table: CapTableBuilder,
         reff: *mut WirePointer,
-        value: StructReader) -> Result<SegmentAnd<*mut Word>>
+        value: StructReader,
+        path: &mut TraversalPath,
+        caps: &mut CapTranslator) -> Result<SegmentAnd<*mut Word>>
     {
         let data_size: WordCount32 = round_bits_up_to_words(value.data_size as u64);
         let total_size: WordCount32 = data_size + value.pointer_count as u32 * WORDS_PER_POINTER as u32;
 
         let (ptr, reff, segment_id) =
-            allocate(arena, reff, segment_id, total_size, WirePointerKind::Struct);
+            try!(allocate(arena, reff, segment_id, total_size, WirePointerKind::Struct));
         (*reff).mut_struct_ref().set(data_size as u16, value.pointer_count);
 
         if value.data_size == 1 {
@@ -1513,10 +1996,13 @@ mod wire_helpers {
 
         let pointer_section: *mut WirePointer = ptr.offset(data_size as isize) as *mut _;
         for i in 0..value.pointer_count as isize {
-            try!(copy_pointer(arena, segment_id, cap_table, pointer_section.offset(i),
-                              value.arena,
-                              value.segment_id, value.cap_table, value.pointers.offset(i),
-                              value.nesting_limit));
+            let dst = PointerBuilder {
+                arena: arena, segment_id: segment_id, cap_table: cap_table,
+                pointer: pointer_section.offset(i),
+            };
+            let src = value.get_pointer_field(i as usize);
+            try!(copy_pointer(dst, src, path, caps)
+                 .map_err(|e| path.record(PathStep::Pointer(i as u32), value.segment_id, src.pointer, e)));
         }
 
         Ok(SegmentAnd { segment_id: segment_id, value: ptr })
@@ -1530,7 +2016,12 @@ mod wire_helpers {
         cap: Box<ClientHook>)
     {
         // TODO if ref is not null, zero object.
-        unsafe { (*reff).set_cap(cap_table.inject_cap(cap) as u32); }
+        if cap.is_null() {
+            // A null capability is written as a null pointer, which reads back as one.
+            unsafe { ptr::write_bytes(reff, 0, 1); }
+        } else {
+            unsafe { (*reff).set_cap(cap_table.inject_cap(cap) as u32); }
+        }
     }
 
     pub unsafe fn set_list_pointer<'a>(
@@ -1538,48 +2029,60 @@ mod wire_helpers {
         segment_id: u32,
         cap_table: CapTableBuilder,
         reff: *mut WirePointer,
-        value: ListReader) -> Result<SegmentAnd<*mut Word>>
+        value: ListReader,
+        path: &mut TraversalPath,
+        caps: &mut CapTranslator) -> Result<SegmentAnd<*mut Word>>
     {
-        let total_size = round_bits_up_to_words((value.element_count * value.step) as u64);
+        let total_size = round_bits_up_to_words(value.element_count as u64 * value.step as u64);
 
         if value.step <= BITS_PER_WORD as u32 {
             //# List of non-structs.
-            let (ptr, reff, segment_id) =
-                allocate(arena, reff, segment_id, total_size, WirePointerKind::List);
-
-            if value.struct_pointer_count == 1 {
-                //# List of pointers.
-                (*reff).mut_list_ref().set(Pointer, value.element_count);
-                for i in 0.. value.element_count as isize {
-                    try!(copy_pointer(arena, segment_id, cap_table,
-                                      (ptr as *mut _).offset(i),
-                                      value.arena,
-                                      value.segment_id, value.cap_table,
-                                      (value.ptr as *const _).offset(i),
-                                      value.nesting_limit));
-                }
+            let element_size = if value.struct_pointer_count == 1 {
+                Pointer
             } else {
-                //# List of data.
-                let element_size = match value.step {
+                match value.step {
                     0 => Void,
                     1 => Bit,
                     8 => Byte,
                     16 => TwoBytes,
                     32 => FourBytes,
                     64 => EightBytes,
-                    _ => { panic!("invalid list step size: {}", value.step) }
-                };
+                    _ => {
+                        return Err(Error::with_reason(ErrorReason::MalformedList, format!("invalid list step size: {}", value.step)));
+                    }
+                }
+            };
 
-                (*reff).mut_list_ref().set(element_size, value.element_count);
+            let (ptr, reff, segment_id) =
+                try!(allocate(arena, reff, segment_id, total_size, WirePointerKind::List));
+            (*reff).mut_list_ref().set(element_size, value.element_count);
+
+            if element_size == Pointer {
+                //# List of pointers.
+                for i in 0.. value.element_count {
+                    let dst = PointerBuilder {
+                        arena: arena, segment_id: segment_id, cap_table: cap_table,
+                        pointer: (ptr as *mut WirePointer).offset(i as isize),
+                    };
+                    let src = value.get_pointer_element(i);
+                    try!(copy_pointer(dst, src, path, caps)
+                         .map_err(|e| path.record(PathStep::ListElement(i), value.segment_id, src.pointer, e)));
+                }
+            } else {
+                //# List of data.
                 ptr::copy_nonoverlapping(value.ptr as *const Word, ptr, total_size as usize);
             }
 
             Ok(SegmentAnd { segment_id: segment_id, value: ptr })
         } else {
             //# List of structs.
+            if value.element_count as u64 * value.step as u64 >= (1 << 29) * BITS_PER_WORD as u64 {
+                return Err(Error::with_reason(ErrorReason::MalformedList,
+                    "Inline composite lists are limited to 2**29 words".to_string()));
+            }
             let (ptr, reff, segment_id) =
-                allocate(arena, reff, segment_id,
-                         total_size + POINTER_SIZE_IN_WORDS as u32, WirePointerKind::List);
+                try!(allocate(arena, reff, segment_id,
+                         total_size + POINTER_SIZE_IN_WORDS as u32, WirePointerKind::List));
             (*reff).mut_list_ref().set_inline_composite(total_size);
 
             let data_size = round_bits_up_to_words(value.struct_data_size as u64);
@@ -1591,16 +2094,26 @@ mod wire_helpers {
             let mut dst = ptr.offset(POINTER_SIZE_IN_WORDS as isize);
 
             let mut src: *const Word = value.ptr as *const _;
-            for _ in 0.. value.element_count {
+            for i in 0.. value.element_count {
                 ptr::copy_nonoverlapping(src, dst,
                                          value.struct_data_size as usize / BITS_PER_WORD);
                 dst = dst.offset(data_size as isize);
                 src = src.offset(data_size as isize);
-
-                for _ in 0..pointer_count {
-                    try!(copy_pointer(arena, segment_id, cap_table, dst as *mut _,
-                                      value.arena, value.segment_id, value.cap_table, src as *const _,
-                                      value.nesting_limit));
+                let element = src as *const WirePointer;
+
+                for j in 0..pointer_count {
+                    let dst_pointer = PointerBuilder {
+                        arena: arena, segment_id: segment_id, cap_table: cap_table,
+                        pointer: dst as *mut _,
+                    };
+                    let src_pointer = PointerReader {
+                        arena: value.arena, segment_id: value.segment_id, cap_table: value.cap_table,
+                        pointer: src as *const _, nesting_limit: value.nesting_limit,
+                    };
+                    try!(copy_pointer(dst_pointer, src_pointer, path, caps)
+                         .map_err(|e| path.record(PathStep::Pointer(j as u32), value.segment_id,
+                                                  src as *const _, e))
+                         .map_err(|e| path.record(PathStep::ListElement(i), value.segment_id, element, e)));
                     dst = dst.offset(POINTER_SIZE_IN_WORDS as isize);
                     src = src.offset(POINTER_SIZE_IN_WORDS as isize);
                 }
@@ -1610,14 +2123,18 @@ mod wire_helpers {
     }
 
     pub unsafe fn copy_pointer(
-        dst_arena: &BuilderArena,
-        dst_segment_id: u32, dst_cap_table: CapTableBuilder,
-        dst: *mut WirePointer,
-        src_arena: &ReaderArena,
-        src_segment_id: u32, src_cap_table: CapTableReader,
-        src: *const WirePointer,
-        nesting_limit: i32) -> Result<SegmentAnd<*mut Word>>
+        dst: PointerBuilder,
+        src: PointerReader,
+        path: &mut TraversalPath,
+        caps: &mut CapTranslator) -> Result<SegmentAnd<*mut Word>>
     {
+        let PointerBuilder {
+            arena: dst_arena, segment_id: dst_segment_id, cap_table: dst_cap_table, pointer: dst,
+        } = dst;
+        let PointerReader {
+            arena: src_arena, segment_id: src_segment_id, cap_table: src_cap_table, pointer: src,
+            nesting_limit,
+        } = src;
         let src_target = (*src).target();
 
         if (*src).is_null() {
@@ -1631,7 +2148,7 @@ mod wire_helpers {
         match (*src).kind() {
             WirePointerKind::Struct => {
                 if nesting_limit <= 0 {
-                    return Err(Error::failed(
+                    return Err(Error::with_reason(ErrorReason::NestingLimitExceeded,
                         "Message is too deeply-nested or contains cycles. See ReaderOptions.".to_string()));
                 }
 
@@ -1651,12 +2168,13 @@ mod wire_helpers {
                         data_size: (*src).struct_ref().data_size.get() as u32 * BITS_PER_WORD as u32,
                         pointer_count: (*src).struct_ref().ptr_count.get(),
                         nesting_limit: nesting_limit - 1
-                    });
+                    },
+                    path, caps);
             }
             WirePointerKind::List => {
                 let element_size = (*src).list_ref().element_size();
                 if nesting_limit <= 0 {
-                    return Err(Error::failed(
+                    return Err(Error::with_reason(ErrorReason::NestingLimitExceeded,
                         "Message is too deeply-nested or contains cycles. See ReaderOptions.".to_string()));
                 }
 
@@ -1670,7 +2188,7 @@ mod wire_helpers {
                         WirePointerKind::List));
 
                     if (*tag).kind() != WirePointerKind::Struct {
-                        return Err(Error::failed(
+                        return Err(Error::with_reason(ErrorReason::MalformedList,
                             "InlineComposite lists of non-STRUCT type are not supported.".to_string()));
                     }
 
@@ -1678,7 +2196,7 @@ mod wire_helpers {
                     let words_per_element = (*tag).struct_ref().word_size();
 
                     if words_per_element as u64 * element_count as u64 > word_count as u64 {
-                        return Err(Error::failed(
+                        return Err(Error::with_reason(ErrorReason::MalformedList,
                             "InlineComposite list's elements overrun its word count.".to_string()));
                     }
 
@@ -1697,11 +2215,13 @@ mod wire_helpers {
                             cap_table: src_cap_table,
                             ptr: ptr as *mut _,
                             element_count: element_count,
+                            element_size: InlineComposite,
                             step: words_per_element * BITS_PER_WORD as u32,
                             struct_data_size: (*tag).struct_ref().data_size.get() as u32 * BITS_PER_WORD as u32,
                             struct_pointer_count: (*tag).struct_ref().ptr_count.get(),
                             nesting_limit: nesting_limit - 1
-                        })
+                        },
+                        path, caps)
                 } else {
                     let data_size = data_bits_per_element(element_size);
                     let pointer_count = pointers_per_element(element_size);
@@ -1728,27 +2248,36 @@ mod wire_helpers {
                             cap_table : src_cap_table,
                             ptr: ptr as *mut _,
                             element_count: element_count,
+                            element_size: element_size,
                             step: step,
                             struct_data_size: data_size,
                             struct_pointer_count: pointer_count as u16,
                             nesting_limit: nesting_limit - 1
-                        })
+                        },
+                        path, caps)
                 }
             }
             WirePointerKind::Far => {
-                Err(Error::failed("Malformed double-far pointer.".to_string()))
+                Err(Error::with_reason(ErrorReason::MalformedFarPointer, "Malformed double-far pointer.".to_string()))
             }
             WirePointerKind::Other => {
                 if !(*src).is_capability() {
-                    return Err(Error::failed("Unknown pointer type.".to_string()));
+                    return Err(Error::with_reason(ErrorReason::UnknownPointerType, "Unknown pointer type.".to_string()));
                 }
                 match src_cap_table.extract_cap((*src).cap_ref().index.get() as usize) {
                     Some(cap) => {
-                        set_capability_pointer(dst_arena, dst_segment_id, dst_cap_table, dst, cap);
+                        match try!(caps(cap)) {
+                            Some(cap) => {
+                                set_capability_pointer(dst_arena, dst_segment_id, dst_cap_table, dst, cap);
+                            }
+                            None => {
+                                ptr::write_bytes(dst, 0, 1);
+                            }
+                        }
                         return Ok(SegmentAnd { segment_id: dst_segment_id, value: ptr::null_mut() });
                     }
                     None => {
-                        return Err(Error::failed(
+                        return Err(Error::with_reason(ErrorReason::InvalidCapability,
                             "Message contained invalid capability pointer.".to_string()));
                     }
                 }
@@ -1777,7 +2306,7 @@ mod wire_helpers {
         }
 
         if nesting_limit <= 0 {
-            return Err(Error::failed("Message is too deeply-nested or contains cycles.".to_string()));
+            return Err(Error::with_reason(ErrorReason::NestingLimitExceeded, "Message is too deeply-nested or contains cycles.".to_string()));
         }
 
         let (ptr, reff, segment_id) = try!(follow_fars(arena, reff, ref_target, segment_id));
@@ -1785,7 +2314,7 @@ mod wire_helpers {
         let data_size_words = (*reff).struct_ref().data_size.get();
 
         if (*reff).kind() != WirePointerKind::Struct {
-            return Err(Error::failed(
+            return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                 "Message contains non-struct pointer where struct pointer was expected.".to_string()));
         }
 
@@ -1805,6 +2334,55 @@ mod wire_helpers {
         })
      }
 
+    pub unsafe fn read_pointer_type(
+        arena: &ReaderArena,
+        segment_id: u32,
+        reff: *const WirePointer) -> Result<any_pointer::Which>
+    {
+        if (*reff).is_null() {
+            return Ok(any_pointer::Which::Null);
+        }
+
+        let is_far = (*reff).kind() == WirePointerKind::Far;
+        let (ptr, reff, segment_id) = try!(follow_fars(arena, reff, (*reff).target(), segment_id));
+
+        match (*reff).kind() {
+            WirePointerKind::Struct => {
+                Ok(any_pointer::Which::Struct {
+                    data_words: (*reff).struct_ref().data_size.get(),
+                    pointer_count: (*reff).struct_ref().ptr_count.get(),
+                })
+            }
+            WirePointerKind::List => {
+                let element_size = (*reff).list_ref().element_size();
+                let element_count = if element_size == InlineComposite {
+                    try!(bounds_check(arena, segment_id, ptr, ptr.offset(1), WirePointerKind::List));
+                    let tag: *const WirePointer = ptr as *const _;
+                    if (*tag).kind() != WirePointerKind::Struct {
+                        return Err(Error::with_reason(ErrorReason::MalformedList,
+                            "InlineComposite lists of non-STRUCT type are not supported.".to_string()));
+                    }
+                    (*tag).inline_composite_list_element_count()
+                } else {
+                    (*reff).list_ref().element_count()
+                };
+                Ok(any_pointer::Which::List { element_size: element_size, element_count: element_count })
+            }
+            WirePointerKind::Far => {
+                Err(Error::with_reason(ErrorReason::MalformedFarPointer,
+                    "Malformed double-far pointer.".to_string()))
+            }
+            WirePointerKind::Other if is_far => {
+                Err(Error::with_reason(ErrorReason::MalformedFarPointer,
+                    "Far pointer lands on a non-struct, non-list pointer.".to_string()))
+            }
+            WirePointerKind::Other if (*reff).is_capability() => {
+                Ok(any_pointer::Which::Capability { index: (*reff).cap_ref().index.get() })
+            }
+            WirePointerKind::Other => Ok(any_pointer::Which::Other),
+        }
+    }
+
     #[inline]
     pub unsafe fn read_capability_pointer(
         _arena: &ReaderArena,
@@ -1814,17 +2392,16 @@ mod wire_helpers {
         _nesting_limit: i32) -> Result<Box<ClientHook>>
     {
         if (*reff).is_null() {
-            Err(Error::failed(
-                "Message contains null capability pointer.".to_string()))
+            Ok(::broken::new_null_cap())
         } else if !(*reff).is_capability() {
-            Err(Error::failed(
+            Err(Error::with_reason(ErrorReason::WrongPointerKind,
                 "Message contains non-capability pointer where capability pointer was expected.".to_string()))
         } else {
             let n = (*reff).cap_ref().index.get() as usize;
             match cap_table.extract_cap(n) {
                 Some(client_hook) => { Ok(client_hook) }
                 None => {
-                    Err(Error::failed(
+                    Err(Error::with_reason(ErrorReason::InvalidCapability,
                         format!("Message contains invalid capability pointer. Index: {}", n)))
                 }
             }
@@ -1851,13 +2428,13 @@ mod wire_helpers {
         }
 
         if nesting_limit <= 0 {
-            return Err(Error::failed("nesting limit exceeded".to_string()));
+            return Err(Error::with_reason(ErrorReason::NestingLimitExceeded, "nesting limit exceeded".to_string()));
         }
 
         let (mut ptr, reff, segment_id) = try!(follow_fars(arena, reff, ref_target, segment_id));
 
         if (*reff).kind() != WirePointerKind::List {
-            return Err(Error::failed(
+            return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                 "Message contains non-list pointer where list pointer was expected".to_string()));
         }
 
@@ -1877,7 +2454,7 @@ mod wire_helpers {
                                   WirePointerKind::List));
 
                 if (*tag).kind() != WirePointerKind::Struct {
-                    return Err(Error::failed(
+                    return Err(Error::with_reason(ErrorReason::MalformedList,
                         "InlineComposite lists of non-STRUCT type are not supported.".to_string()));
                 }
 
@@ -1886,7 +2463,7 @@ mod wire_helpers {
                 let words_per_element = struct_ref.word_size();
 
                 if size as u64 * words_per_element as u64 > word_count as u64 {
-                    return Err(Error::failed(
+                    return Err(Error::with_reason(ErrorReason::MalformedList,
                          "InlineComposite list's elements overrun its word count.".to_string()));
                 }
 
@@ -1905,12 +2482,12 @@ mod wire_helpers {
                 match expected_element_size {
                     Void => {}
                     Bit => {
-                        return Err(Error::failed(
+                        return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                             "Found struct list where bit list was expected.".to_string()));
                     }
                     Byte | TwoBytes | FourBytes | EightBytes => {
                         if struct_ref.data_size.get() <= 0 {
-                            return Err(Error::failed(
+                            return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                                 "Expected a primitive list, but got a list of pointer-only structs".to_string()));
                         }
                     }
@@ -1920,7 +2497,7 @@ mod wire_helpers {
                         // munge the pointer to point at the first element's pointer section.
                         ptr = ptr.offset(struct_ref.data_size.get() as isize);
                         if struct_ref.ptr_count.get() <= 0 {
-                            return Err(Error::failed(
+                            return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                                 "Expected a pointer list, but got a list of data-only structs".to_string()));
                         }
                     }
@@ -1933,6 +2510,7 @@ mod wire_helpers {
                     cap_table: cap_table,
                     ptr: ptr as *const _,
                     element_count: size,
+                    element_size: InlineComposite,
                     step: words_per_element * BITS_PER_WORD as u32,
                     struct_data_size: struct_ref.data_size.get() as u32 * (BITS_PER_WORD as u32),
                     struct_pointer_count: struct_ref.ptr_count.get(),
@@ -1968,7 +2546,7 @@ mod wire_helpers {
 
                 if expected_data_bits_per_element > data_size ||
                     expected_pointers_per_element > pointer_count {
-                    return Err(Error::failed(
+                    return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                         "Message contains list with incompatible element type.".to_string()));
                 }
 
@@ -1978,6 +2556,7 @@ mod wire_helpers {
                     cap_table: cap_table,
                     ptr: ptr as *const _,
                     element_count: list_ref.element_count(),
+                    element_size: element_size,
                     step: step,
                     struct_data_size: data_size,
                     struct_pointer_count: pointer_count as u16,
@@ -1996,7 +2575,9 @@ mod wire_helpers {
         default_size: ByteCount32) -> Result<text::Reader<'a>>
     {
         if (*reff).is_null() {
-            //   TODO?       if default_value.is_null() { default_value = &"" }
+            if default_value.is_null() {
+                return text::new_reader(&[]);
+            }
             return text::new_reader(
                 slice::from_raw_parts(mem::transmute(default_value), default_size as usize));
         }
@@ -2007,12 +2588,12 @@ mod wire_helpers {
         let size = list_ref.element_count();
 
         if (*reff).kind() != WirePointerKind::List {
-            return Err(Error::failed(
+            return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                 "Message contains non-list pointer where text was expected.".to_string()));
         }
 
         if list_ref.element_size() != Byte {
-            return Err(Error::failed(
+            return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                 "Message contains list pointer of non-bytes where text was expected.".to_string()));
         }
 
@@ -2021,13 +2602,13 @@ mod wire_helpers {
                           WirePointerKind::List));
 
         if size <= 0 {
-            return Err(Error::failed("Message contains text that is not NUL-terminated.".to_string()));
+            return Err(Error::with_reason(ErrorReason::MissingNulTerminator, "Message contains text that is not NUL-terminated.".to_string()));
         }
 
         let str_ptr = ptr as *const u8;
 
         if (*str_ptr.offset((size - 1) as isize)) != 0u8 {
-            return Err(Error::failed(
+            return Err(Error::with_reason(ErrorReason::MissingNulTerminator,
                 "Message contains text that is not NUL-terminated".to_string()));
         }
 
@@ -2043,6 +2624,9 @@ mod wire_helpers {
         default_size: ByteCount32) -> Result<data::Reader<'a>>
     {
         if (*reff).is_null() {
+            if default_value.is_null() {
+                return Ok(&[]);
+            }
             return Ok(data::new_reader(default_value as *const _, default_size));
         }
 
@@ -2055,12 +2639,12 @@ mod wire_helpers {
         let size: u32 = list_ref.element_count();
 
         if (*reff).kind() != WirePointerKind::List {
-            return Err(Error::failed(
+            return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                 "Message contains non-list pointer where data was expected.".to_string()));
         }
 
         if list_ref.element_size() != Byte {
-            return Err(Error::failed(
+            return Err(Error::with_reason(ErrorReason::WrongPointerKind,
                 "Message contains list pointer of non-bytes where data was expected.".to_string()));
         }
 
@@ -2079,6 +2663,11 @@ static NULL_ARENA: NullArena = NullArena;
 
 pub type CapTable = Vec<Option<Box<ClientHook>>>;
 
+/// Decides what becomes of each capability met during a deep copy. Returning `Ok(Some(cap))`
+/// stores `cap` in the destination, `Ok(None)` leaves a null pointer in its place, and `Err`
+/// aborts the copy.
+pub type CapTranslator<'t> = FnMut(Box<ClientHook>) -> Result<Option<Box<ClientHook>>> + 't;
+
 #[derive(Copy, Clone)]
 pub enum CapTableReader {
     // At one point, we had a `Dummy` variant here, but that ended up
@@ -2229,7 +2818,31 @@ impl <'a> PointerReader<'a> {
         if self.pointer.is_null() {
             Ok( MessageSize { word_count: 0, cap_count: 0 } )
         } else {
-            unsafe { wire_helpers::total_size(self.arena, self.segment_id, self.pointer, self.nesting_limit) }
+            let mut path = wire_helpers::TraversalPath::new();
+            unsafe {
+                wire_helpers::total_size(self.arena, self.segment_id, self.pointer,
+                                         self.nesting_limit, &mut path)
+                    .map_err(|e| {
+                        let e = path.record(::PathStep::Root, self.segment_id, self.pointer, e);
+                        path.annotate(self.arena, e)
+                    })
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.pointer.is_null() {
+            Ok(())
+        } else {
+            let mut path = wire_helpers::TraversalPath::new();
+            unsafe {
+                wire_helpers::validate(self.arena, self.segment_id, self.pointer,
+                                       self.nesting_limit, &mut path)
+                    .map_err(|e| {
+                        let e = path.record(::PathStep::Root, self.segment_id, self.pointer, e);
+                        path.annotate(self.arena, e)
+                    })
+            }
         }
     }
 
@@ -2239,6 +2852,7 @@ impl <'a> PointerReader<'a> {
             wire_helpers::read_struct_pointer(self.arena,
                                               self.segment_id, self.cap_table, reff,
                                               default_value, self.nesting_limit)
+                .map_err(|e| wire_helpers::at_location(self.arena, self.segment_id, reff, e))
         }
     }
 
@@ -2253,6 +2867,7 @@ impl <'a> PointerReader<'a> {
                 reff,
                 default_value,
                 expected_element_size, self.nesting_limit)
+                .map_err(|e| wire_helpers::at_location(self.arena, self.segment_id, reff, e))
         }
     }
 
@@ -2260,6 +2875,7 @@ impl <'a> PointerReader<'a> {
         let reff = if self.pointer.is_null() { zero_pointer() } else { self.pointer };
         unsafe {
             wire_helpers::read_text_pointer(self.arena, self.segment_id, reff, default_value, default_size)
+                .map_err(|e| wire_helpers::at_location(self.arena, self.segment_id, reff, e))
         }
     }
 
@@ -2267,6 +2883,17 @@ impl <'a> PointerReader<'a> {
         let reff = if self.pointer.is_null() { zero_pointer() } else { self.pointer };
         unsafe {
             wire_helpers::read_data_pointer(self.arena, self.segment_id, reff, default_value, default_size)
+                .map_err(|e| wire_helpers::at_location(self.arena, self.segment_id, reff, e))
+        }
+    }
+
+    pub fn get_pointer_type(&self) -> Result<::any_pointer::Which> {
+        if self.pointer.is_null() {
+            return Ok(::any_pointer::Which::Null);
+        }
+        unsafe {
+            wire_helpers::read_pointer_type(self.arena, self.segment_id, self.pointer)
+                .map_err(|e| wire_helpers::at_location(self.arena, self.segment_id, self.pointer, e))
         }
     }
 
@@ -2275,10 +2902,23 @@ impl <'a> PointerReader<'a> {
         unsafe {
             wire_helpers::read_capability_pointer(
                 self.arena, self.segment_id, self.cap_table, reff, self.nesting_limit)
+                .map_err(|e| wire_helpers::at_location(self.arena, self.segment_id, reff, e))
         }
     }
 }
 
+/// Unwraps the result of replacing the object that a builder's pointer points to. That only fails
+/// if zeroing the old object runs into a malformed pointer, and a builder never holds one: its
+/// segments start out zeroed (see `message::Allocator`), and every pointer in them is written by
+/// this module, from values that were checked as they were copied in. An error here therefore
+/// means that the message's memory has been corrupted, which no caller can handle.
+pub fn expect_well_formed<T>(result: Result<T>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(e) => panic!("Builder message is corrupt: {}", e),
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct PointerBuilder<'a> {
     arena: &'a BuilderArena,
@@ -2374,65 +3014,76 @@ impl <'a> PointerBuilder<'a> {
 
     pub fn init_struct(self, size: StructSize) -> StructBuilder<'a> {
         unsafe {
-            wire_helpers::init_struct_pointer(self.arena, self.pointer, self.segment_id, self.cap_table, size)
+            expect_well_formed(wire_helpers::init_struct_pointer(
+                self.arena, self.pointer, self.segment_id, self.cap_table, size))
         }
     }
 
     pub fn init_list(self, element_size: ElementSize, element_count: ElementCount32) -> ListBuilder<'a> {
         unsafe {
-            wire_helpers::init_list_pointer(
-                self.arena, self.pointer, self.segment_id, self.cap_table, element_count, element_size)
+            expect_well_formed(wire_helpers::init_list_pointer(
+                self.arena, self.pointer, self.segment_id, self.cap_table, element_count, element_size))
         }
     }
 
     pub fn init_struct_list(self, element_count: ElementCount32, element_size: StructSize)
                             -> ListBuilder<'a> {
         unsafe {
-            wire_helpers::init_struct_list_pointer(
+            expect_well_formed(wire_helpers::init_struct_list_pointer(
                 self.arena,
                 self.pointer, self.segment_id,
-                self.cap_table, element_count, element_size)
+                self.cap_table, element_count, element_size))
         }
     }
 
     pub fn init_text(self, size: ByteCount32) -> text::Builder<'a> {
         unsafe {
-            wire_helpers::init_text_pointer(self.arena, self.pointer, self.segment_id, size).value
+            expect_well_formed(
+                wire_helpers::init_text_pointer(self.arena, self.pointer, self.segment_id, size)).value
         }
     }
 
     pub fn init_data(self, size: ByteCount32) -> data::Builder<'a> {
         unsafe {
-            wire_helpers::init_data_pointer(self.arena, self.pointer, self.segment_id, size).value
+            expect_well_formed(
+                wire_helpers::init_data_pointer(self.arena, self.pointer, self.segment_id, size)).value
         }
     }
 
     pub fn set_struct(&self, value: &StructReader) -> Result<()> {
         unsafe {
+            let mut path = wire_helpers::TraversalPath::new();
             try!(wire_helpers::set_struct_pointer(
                 self.arena,
-                self.segment_id, self.cap_table, self.pointer, *value));
+                self.segment_id, self.cap_table, self.pointer, *value, &mut path,
+                &mut wire_helpers::keep_cap)
+                 .map_err(|e| path.annotate(value.arena, e)));
             Ok(())
         }
     }
 
     pub fn set_list(&self, value: &ListReader) -> Result<()> {
         unsafe {
+            let mut path = wire_helpers::TraversalPath::new();
             try!(wire_helpers::set_list_pointer(self.arena, self.segment_id,
-                                                self.cap_table, self.pointer, *value));
+                                                self.cap_table, self.pointer, *value, &mut path,
+                                                &mut wire_helpers::keep_cap)
+                 .map_err(|e| path.annotate(value.arena, e)));
             Ok(())
         }
     }
 
     pub fn set_text(&self, value: &str) {
         unsafe {
-            wire_helpers::set_text_pointer(self.arena, self.pointer, self.segment_id, value);
+            expect_well_formed(
+                wire_helpers::set_text_pointer(self.arena, self.pointer, self.segment_id, value));
         }
     }
 
     pub fn set_data(&self, value: &[u8]) {
         unsafe {
-            wire_helpers::set_data_pointer(self.arena, self.pointer, self.segment_id, value);
+            expect_well_formed(
+                wire_helpers::set_data_pointer(self.arena, self.pointer, self.segment_id, value));
         }
     }
 
@@ -2442,29 +3093,44 @@ impl <'a> PointerBuilder<'a> {
     }
 
     pub fn copy_from(&mut self, other: PointerReader) -> Result<()> {
+        self.copy_from_with_caps(other, &mut wire_helpers::keep_cap)
+    }
+
+    /// Like `copy_from()`, but passes every capability in `other` through `caps` before storing
+    /// it in this message's cap table. If the copy fails, this pointer is left null.
+    pub fn copy_from_with_caps(&mut self, other: PointerReader, caps: &mut CapTranslator)
+                               -> Result<()>
+    {
         if other.pointer.is_null()  {
             if !self.pointer.is_null() {
                 unsafe {
-                    wire_helpers::zero_object(self.arena, self.segment_id, self.pointer);
+                    try!(wire_helpers::zero_object(self.arena, self.segment_id, self.pointer));
                     *self.pointer = mem::zeroed();
                 }
             }
         } else {
+            let mut path = wire_helpers::TraversalPath::new();
             unsafe {
-                try!(wire_helpers::copy_pointer(self.arena, self.segment_id, self.cap_table, self.pointer,
-                                                other.arena,
-                                                other.segment_id, other.cap_table, other.pointer,
-                                                other.nesting_limit));
+                let result = wire_helpers::copy_pointer(*self, other, &mut path, caps);
+                if let Err(e) = result {
+                    // Don't leave a partial copy behind. The copy's own error is the one worth
+                    // reporting, so a failure to zero what was copied so far is dropped.
+                    let _ = wire_helpers::zero_object(self.arena, self.segment_id, self.pointer);
+                    *self.pointer = mem::zeroed();
+                    let e = path.record(::PathStep::Root, other.segment_id, other.pointer, e);
+                    return Err(path.annotate(other.arena, e));
+                }
             }
         }
         Ok(())
     }
 
-    pub fn clear(&mut self) {
+    pub fn clear(&mut self) -> Result<()> {
         unsafe {
-            wire_helpers::zero_object(self.arena, self.segment_id, self.pointer);
+            try!(wire_helpers::zero_object(self.arena, self.segment_id, self.pointer));
             ptr::write_bytes(self.pointer, 0, 1);
         }
+        Ok(())
     }
 
     pub fn as_reader(self) -> PointerReader<'a> {
@@ -2511,7 +3177,20 @@ impl <'a> StructReader<'a> {
 
     pub fn get_pointer_section_size(&self) -> WirePointerCount16 { self.pointer_count }
 
-    pub fn get_data_section_as_blob(&self) -> usize { panic!("unimplemented") }
+    /// The data section as raw bytes. A struct element of a bit list has a data section
+    /// narrower than a byte, so it gets an empty slice; read its bit with `get_bool_field()`.
+    pub fn get_data_section_as_blob(&self) -> &'a [u8] {
+        if self.data.is_null() {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(self.data, self.data_size as usize / BITS_PER_BYTE) }
+        }
+    }
+
+    /// Iterates over the pointer section, in field order.
+    pub fn iter_pointer_section(self) -> ListIter<StructReader<'a>, PointerReader<'a>> {
+        ListIter::new(self, self.pointer_count as u32)
+    }
 
     #[inline]
     pub fn get_data_field<T: Endian + zero::Zero>(&self, offset: ElementCount) -> T {
@@ -2577,10 +3256,16 @@ impl <'a> StructReader<'a> {
             cap_count: 0 };
 
         for i in 0.. self.pointer_count as isize {
+            let mut path = wire_helpers::TraversalPath::new();
             unsafe {
+                let pointer = self.pointers.offset(i);
                 result.plus_eq(try!(wire_helpers::total_size(
-                    self.arena, self.segment_id, self.pointers.offset(i),
-                    self.nesting_limit)));
+                    self.arena, self.segment_id, pointer, self.nesting_limit, &mut path)
+                    .map_err(|e| {
+                        let e = path.record(::PathStep::Pointer(i as u32), self.segment_id,
+                                            pointer, e);
+                        path.annotate(self.arena, e)
+                    })));
             }
         }
 
@@ -2619,6 +3304,85 @@ impl <'a> StructBuilder<'a> {
         self.cap_table = cap_table
     }
 
+    pub fn get_data_section_size(&self) -> BitCount32 { self.data_size }
+
+    pub fn get_pointer_section_size(&self) -> WirePointerCount16 { self.pointer_count }
+
+    /// The data section as raw bytes, which may be written through. As with
+    /// `StructReader::get_data_section_as_blob()`, the element of a bit list gets an empty slice.
+    pub fn get_data_section_mut(self) -> &'a mut [u8] {
+        if self.data.is_null() {
+            &mut []
+        } else {
+            unsafe { slice::from_raw_parts_mut(self.data, self.data_size as usize / BITS_PER_BYTE) }
+        }
+    }
+
+    /// Iterates over the pointer section, in field order.
+    pub fn iter_pointer_section(self) -> ListIter<StructBuilder<'a>, PointerBuilder<'a>> {
+        ListIter::new(self, self.pointer_count as u32)
+    }
+
+    /// Overwrites this struct with the contents of `other`. Data and pointers that do not fit
+    /// in this struct are dropped; fields that `other` lacks are zeroed. Pointers are deep-copied.
+    pub fn copy_content_from(&self, other: &StructReader) -> Result<()> {
+        use std::cmp::min;
+        let shared_data_size = min(self.data_size, other.data_size);
+        let shared_pointer_count = min(self.pointer_count, other.pointer_count);
+
+        if (shared_data_size > 0 && ptr::eq(other.data, self.data)) ||
+            (shared_pointer_count > 0 && ptr::eq(other.pointers, self.pointers)) {
+            // At least one of the section pointers is pointing to ourself. Verify that the other
+            // is too (but ignore empty sections).
+            assert!((shared_data_size == 0 || ptr::eq(other.data, self.data)) &&
+                    (shared_pointer_count == 0 || ptr::eq(other.pointers, self.pointers)),
+                    "Tried to copy a struct into itself?");
+            // This is a copy from ourself, nothing to do.
+            return Ok(());
+        }
+
+        unsafe {
+            if self.data_size > shared_data_size {
+                // Since the target is larger than the source, make sure to zero out the extra
+                // bits that the source doesn't have.
+                if self.data_size == 1 {
+                    self.set_bool_field(0, false);
+                } else {
+                    let unshared = self.data.add(shared_data_size as usize / BITS_PER_BYTE);
+                    ptr::write_bytes(unshared, 0,
+                                     (self.data_size - shared_data_size) as usize / BITS_PER_BYTE);
+                }
+            }
+
+            // Copy over the shared part.
+            if shared_data_size == 1 {
+                self.set_bool_field(0, other.get_bool_field(0));
+            } else {
+                ptr::copy_nonoverlapping(other.data, self.data,
+                                         shared_data_size as usize / BITS_PER_BYTE);
+            }
+
+            // Zero out all pointers in the target.
+            for i in 0..self.pointer_count as isize {
+                try!(wire_helpers::zero_object(self.arena, self.segment_id, self.pointers.offset(i)));
+            }
+            ptr::write_bytes(self.pointers, 0u8, self.pointer_count as usize);
+
+            for i in 0..shared_pointer_count as isize {
+                let mut path = wire_helpers::TraversalPath::new();
+                let src = other.get_pointer_field(i as usize);
+                try!(wire_helpers::copy_pointer(self.get_pointer_field(i as usize), src, &mut path,
+                                                &mut wire_helpers::keep_cap)
+                     .map_err(|e| {
+                         let e = path.record(::PathStep::Pointer(i as u32), other.segment_id,
+                                             src.pointer, e);
+                         path.annotate(other.arena, e)
+                     }));
+            }
+        }
+        Ok(())
+    }
+
     #[inline]
     pub fn set_data_field<T:Endian>(&self, offset: ElementCount, value: T) {
         unsafe {
@@ -2696,6 +3460,18 @@ impl <'a> StructBuilder<'a> {
 
 }
 
+impl <'a> IndexMove<u32, PointerReader<'a>> for StructReader<'a> {
+    fn index_move(&self, index: u32) -> PointerReader<'a> {
+        self.get_pointer_field(index as usize)
+    }
+}
+
+impl <'a> IndexMove<u32, PointerBuilder<'a>> for StructBuilder<'a> {
+    fn index_move(&self, index: u32) -> PointerBuilder<'a> {
+        self.get_pointer_field(index as usize)
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct ListReader<'a> {
     arena: &'a ReaderArena,
@@ -2703,6 +3479,7 @@ pub struct ListReader<'a> {
     ptr: *const u8,
     segment_id: u32,
     element_count: ElementCount32,
+    element_size: ElementSize,
     step: BitCount32,
     struct_data_size: BitCount32,
     nesting_limit: i32,
@@ -2717,6 +3494,7 @@ impl <'a> ListReader<'a> {
             cap_table: CapTableReader::Plain(ptr::null()),
             ptr: ptr::null(),
             element_count: 0,
+            element_size: Void,
             step: 0,
             struct_data_size: 0,
             struct_pointer_count: 0,
@@ -2731,6 +3509,22 @@ impl <'a> ListReader<'a> {
     #[inline]
     pub fn len(&self) -> ElementCount32 { self.element_count }
 
+    #[inline]
+    pub fn get_element_size(&self) -> ElementSize { self.element_size }
+
+    #[inline]
+    pub fn get_step_size_in_bits(&self) -> BitCount32 { self.step }
+
+    /// The bytes spanned by the list's elements, rounded down to a whole byte.
+    pub fn into_raw_bytes(self) -> &'a [u8] {
+        if self.element_count == 0 {
+            &[]
+        } else {
+            let num_bytes = (self.element_count as u64 * self.step as u64 / BITS_PER_BYTE as u64) as usize;
+            unsafe { slice::from_raw_parts(self.ptr, num_bytes) }
+        }
+    }
+
     #[inline]
     pub fn get_struct_element(&self, index: ElementCount32) -> StructReader<'a> {
         let index_byte: ByteCount32 =
@@ -2774,9 +3568,15 @@ pub struct ListBuilder<'a> {
     ptr: *mut u8,
     segment_id: u32,
     element_count: ElementCount32,
+    element_size: ElementSize,
     step: BitCount32,
     struct_data_size: BitCount32,
-    struct_pointer_count: WirePointerCount16
+    struct_pointer_count: WirePointerCount16,
+
+    // The pointer that refers to this list, needed to resize it. Null if the list has no
+    // location in a message.
+    origin: *mut WirePointer,
+    origin_segment_id: u32,
 }
 
 impl <'a> ListBuilder<'a> {
@@ -2789,9 +3589,12 @@ impl <'a> ListBuilder<'a> {
             cap_table: CapTableBuilder::Plain(ptr::null_mut()),
             ptr: ptr::null_mut(),
             element_count: 0,
+            element_size: Void,
             step: 0,
             struct_data_size: 0,
             struct_pointer_count: 0,
+            origin: ptr::null_mut(),
+            origin_segment_id: 0,
         }
     }
 
@@ -2802,6 +3605,7 @@ impl <'a> ListBuilder<'a> {
             cap_table: self.cap_table.as_reader(),
             ptr: self.ptr as *const _,
             element_count: self.element_count,
+            element_size: self.element_size,
             step: self.step,
             struct_data_size: self.struct_data_size,
             struct_pointer_count: self.struct_pointer_count,
@@ -2820,6 +3624,35 @@ impl <'a> ListBuilder<'a> {
     #[inline]
     pub fn len(&self) -> ElementCount32 { self.element_count }
 
+    #[inline]
+    pub fn get_element_size(&self) -> ElementSize { self.element_size }
+
+    /// Shrinks the list in place to `new_len` elements, zeroing the removed elements and
+    /// everything they point to. Errors if `new_len` is greater than the current length.
+    pub fn truncate(&mut self, new_len: ElementCount32) -> Result<()> {
+        unsafe { wire_helpers::truncate_list(self, new_len) }
+    }
+
+    /// Sets the length of the list. Shrinking happens in place, as in `truncate()`. Growing moves
+    /// the elements to a new, larger allocation and zeroes the old one; the new elements are
+    /// zero. Either way, other builders for this list or its elements become out of date.
+    pub fn resize(&mut self, new_len: ElementCount32) -> Result<()> {
+        unsafe { wire_helpers::resize_list(self, new_len) }
+    }
+
+    #[inline]
+    pub fn get_step_size_in_bits(&self) -> BitCount32 { self.step }
+
+    /// The bytes spanned by the list's elements, rounded down to a whole byte.
+    pub fn into_raw_bytes(self) -> &'a mut [u8] {
+        if self.element_count == 0 {
+            &mut []
+        } else {
+            let num_bytes = (self.element_count as u64 * self.step as u64 / BITS_PER_BYTE as u64) as usize;
+            unsafe { slice::from_raw_parts_mut(self.ptr, num_bytes) }
+        }
+    }
+
     #[inline]
     pub fn get_struct_element(self, index: ElementCount32) -> StructBuilder<'a> {
         let index_byte = ((index as u64 * self.step as u64) / BITS_PER_BYTE as u64) as u32;
@@ -2850,6 +3683,104 @@ impl <'a> ListBuilder<'a> {
     }
 }
 
+/// The bytes of a text or data blob that grows as it is appended to. While the blob is being
+/// built, the list on the wire spans its whole capacity, and the capacity at least doubles each
+/// time it runs out, so appending n bytes costs O(n) time and abandons O(n) words at most.
+/// `finish()` trims the list back to the bytes written plus `terminator` trailing zero bytes.
+pub struct GrowableBlob<'a> {
+    pointer: PointerBuilder<'a>,
+    // The whole list on the wire. Everything past `len` is zero.
+    bytes: &'a mut [u8],
+    len: usize,
+    terminator: usize,
+}
+
+impl <'a> GrowableBlob<'a> {
+    /// Takes over the byte list that `pointer` refers to, of which the first `len` bytes are
+    /// the contents. A null pointer stands for an empty blob.
+    pub fn new(pointer: PointerBuilder<'a>, len: u32, terminator: u32) -> Result<GrowableBlob<'a>> {
+        let bytes = try!(pointer.get_list(Byte, ptr::null())).into_raw_bytes();
+        let (len, terminator) = (len as usize, terminator as usize);
+        if !(bytes.is_empty() && len == 0) && len + terminator > bytes.len() {
+            return Err(Error::failed(
+                format!("Blob of {} bytes does not fit in a list of {} bytes.", len + terminator, bytes.len())));
+        }
+        Ok(GrowableBlob { pointer: pointer, bytes: bytes, len: len, terminator: terminator })
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize { self.len }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] { &self.bytes[..self.len] }
+
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [u8] { &mut self.bytes[..self.len] }
+
+    // Re-reads the list, making sure that nothing else has moved or resized it in the meantime.
+    fn list(&self) -> Result<ListBuilder<'a>> {
+        let list = try!(self.pointer.get_list(Byte, ptr::null()));
+        if list.len() as usize != self.bytes.len() ||
+            (!self.bytes.is_empty() && !ptr::eq(list.ptr, self.bytes.as_ptr()))
+        {
+            return Err(Error::failed(
+                "Growable blob is out of date; its list has been moved or replaced.".to_string()));
+        }
+        Ok(list)
+    }
+
+    /// Makes room for at least `additional` more bytes.
+    pub fn reserve(&mut self, additional: usize) -> Result<()> {
+        const MAX_LEN: usize = (1 << 29) - 1;
+        let needed = self.len.saturating_add(self.terminator).saturating_add(additional);
+        if needed <= self.bytes.len() {
+            return Ok(());
+        }
+        if needed > MAX_LEN {
+            return Err(Error::failed(
+                format!("Blob of {} bytes exceeds the limit of {} bytes.", needed, MAX_LEN)));
+        }
+        let capacity = ::std::cmp::max(needed, ::std::cmp::min(2 * self.bytes.len(), MAX_LEN));
+        let mut list = try!(self.list());
+        try!(list.resize(capacity as u32));
+        self.bytes = list.into_raw_bytes();
+        Ok(())
+    }
+
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) -> Result<()> {
+        try!(self.reserve(bytes.len()));
+        self.bytes[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+        Ok(())
+    }
+
+    /// Shortens the contents to `len` bytes. Panics if `len` is greater than the current length.
+    pub fn truncate(&mut self, len: usize) {
+        assert!(len <= self.len, "cannot truncate a blob of {} bytes to {} bytes", self.len, len);
+        for byte in &mut self.bytes[len..self.len] {
+            *byte = 0;
+        }
+        self.len = len;
+    }
+
+    /// Trims the list on the wire down to the contents and the terminator, and returns the
+    /// contents. The blob is empty afterwards, so finishing it again does nothing.
+    pub fn finish(&mut self) -> Result<&'a mut [u8]> {
+        let wire_len = self.len + self.terminator;
+        if self.bytes.len() > wire_len {
+            let mut list = try!(self.list());
+            try!(list.truncate(wire_len as u32));
+        }
+        let bytes = mem::replace(&mut self.bytes, &mut []);
+        let len = mem::replace(&mut self.len, 0);
+        self.terminator = 0;
+        Ok(&mut bytes[..len])
+    }
+}
+
 
 pub trait PrimitiveElement: Endian {
     #[inline]
@@ -167,3 +167,258 @@ fn struct_list_size() {
 
     assert_eq!(pointer_reader.total_size().unwrap().word_count, 2);
 }
+
+fn small_segment_message() -> ::message::Builder<::message::HeapAllocator> {
+    ::message::Builder::new(
+        ::message::HeapAllocator::new()
+            .first_segment_words(4)
+            .allocation_strategy(::message::AllocationStrategy::FixedSize))
+}
+
+fn nonzero_words<A: ::message::Allocator>(message: &::message::Builder<A>) -> usize {
+    let segments = message.get_segments_for_output();
+    segments.iter().map(|segment| {
+        ::Word::words_to_bytes(segment).chunks(8).filter(|word| word.iter().any(|&b| b != 0)).count()
+    }).sum()
+}
+
+#[test]
+fn resize_primitive_list() {
+    let mut message = small_segment_message();
+    {
+        // A null list still knows where it lives.
+        let mut list: ::primitive_list::Builder<u32> = message.get_root().unwrap();
+        assert_eq!(list.len(), 0);
+        list.resize(3).unwrap();
+        list.copy_from_slice(&[1, 2, 3]);
+
+        // Too big for the first segment, so the list moves behind a far pointer.
+        list.resize(20).unwrap();
+        assert_eq!(list.get(2), 3);
+        assert_eq!(list.get(19), 0);
+        list.set(19, 19);
+
+        list.truncate(2).unwrap();
+        assert_eq!(list.len(), 2);
+        assert!(list.truncate(3).unwrap_err().has_reason(::ErrorReason::IndexOutOfRange));
+        assert_eq!(list.len(), 2);
+    }
+
+    let list: ::primitive_list::Reader<u32> = message.get_root_as_reader().unwrap();
+    assert_eq!(list.iter().collect::<Vec<_>>(), vec![1, 2]);
+
+    // The far pointer, its landing pad and one word of data. Everything else was zeroed.
+    assert_eq!(nonzero_words(&message), 3);
+}
+
+#[test]
+fn resize_bit_list() {
+    let mut message = small_segment_message();
+    {
+        let mut list: ::primitive_list::Builder<bool> =
+            message.init_root::<::any_pointer::Builder>().initn_as(10);
+        for idx in 0..10 {
+            list.set(idx, true);
+        }
+        list.truncate(3).unwrap();
+        list.resize(12).unwrap();
+    }
+    let list: ::primitive_list::Reader<bool> = message.get_root_as_reader().unwrap();
+    assert_eq!(list.iter().collect::<Vec<_>>(),
+               vec![true, true, true, false, false, false, false, false, false, false, false, false]);
+}
+
+#[test]
+fn resize_detached_list() {
+    // A list builder that belongs to no message has no pointer to move it through.
+    let mut list = ::private::layout::ListBuilder::new_default();
+    assert!(list.resize(3).is_err());
+}
+
+#[test]
+fn resize_pointer_and_struct_lists() {
+    let mut message = small_segment_message();
+    {
+        let mut st = message.init_root::<::any_pointer::Builder>().init_any_struct(0, 2);
+        let mut texts: ::text_list::Builder =
+            st.borrow().get_pointer_field(0).init_from_iter(vec!["a", "bb", "ccc"]);
+        texts.resize(40).unwrap();
+        texts.set(39, "last");
+        texts.truncate(2).unwrap();
+
+        let mut points = st.get_pointer_field(1).init_any_struct_list(2, 1, 1);
+        for idx in 0..2 {
+            let mut point = points.borrow().get_struct_element(idx);
+            point.borrow().get_data_section()[0] = idx as u8 + 1;
+            point.get_pointer_field(0).set_as::<::text::Builder, _>("point").unwrap();
+        }
+        points.resize(5).unwrap();
+        assert_eq!(points.len(), 5);
+        points.truncate(1).unwrap();
+    }
+
+    let st: ::any_struct::Reader = message.get_root_as_reader().unwrap();
+    let texts: ::text_list::Reader = st.get_pointer_field(0).get_as().unwrap();
+    assert_eq!(texts.iter().map(|t| t.unwrap()).collect::<Vec<_>>(), vec!["a", "bb"]);
+
+    let points: ::any_list::Reader = st.get_pointer_field(1).get_as().unwrap();
+    assert_eq!(points.len(), 1);
+    let point = points.get_struct_element(0);
+    assert_eq!(point.get_data_section()[0], 1);
+    assert_eq!(point.get_pointer_field(0).get_as::<::text::Reader>().unwrap(), "point");
+
+    // Only what is still reachable takes up space: the struct, the text list with its two
+    // texts, and the one-element struct list (tag, element and text).
+    assert_eq!(st.total_size().unwrap().word_count, 2 + (2 + 2) + (1 + 2 + 1));
+}
+
+#[test]
+fn grow_text_and_data() {
+    use std::fmt::Write as FmtWrite;
+    use std::io::Write as IoWrite;
+
+    let mut message = small_segment_message();
+    let mut st = message.init_root::<::any_pointer::Builder>().init_any_struct(0, 2);
+    {
+        let mut text: ::text::GrowableBuilder = st.borrow().get_pointer_field(0).init_as();
+        text.push_str("hello").unwrap();
+
+        // The text is the last allocation in its segment, so this extends it in place.
+        text.push(',').unwrap();
+    }
+    {
+        let mut data: ::data::GrowableBuilder = st.borrow().get_pointer_field(1).init_as();
+        data.write_all(&[1, 2, 3]).unwrap();
+    }
+    {
+        // The data now sits after the text, so growing the text moves it.
+        let mut text: ::text::GrowableBuilder = st.borrow().get_pointer_field(0).get_as().unwrap();
+        write!(text, " {}!", "world").unwrap();
+        assert_eq!(&*text, "hello, world!");
+    }
+    {
+        let mut data: ::data::GrowableBuilder = st.borrow().get_pointer_field(1).get_as().unwrap();
+        data.extend_from_slice(&[4; 20]).unwrap();
+        data.truncate(5);
+        data[4] = 5;
+        assert_eq!(data.into_builder().unwrap(), &[1, 2, 3, 4, 5]);
+    }
+
+    let st: ::any_struct::Reader = message.get_root_as_reader().unwrap();
+    assert_eq!(st.get_pointer_field(0).get_as::<::text::Reader>().unwrap(), "hello, world!");
+    assert_eq!(st.get_pointer_field(1).get_as::<::data::Reader>().unwrap(), &[1, 2, 3, 4, 5]);
+    assert_eq!(st.total_size().unwrap().word_count, 2 + 2 + 1);
+}
+
+#[test]
+fn grow_existing_text() {
+    let mut message = ::message::Builder::new_default();
+    message.set_root::<::text::Builder, _>("abc").unwrap();
+    {
+        let mut text: ::text::GrowableBuilder = message.get_root().unwrap();
+        text.push_str("defghijkl").unwrap();
+    }
+    assert_eq!(message.get_root_as_reader::<::text::Reader>().unwrap(), "abcdefghijkl");
+}
+
+#[test]
+fn stream_into_growable_blobs() {
+    // Every segment is filled by the allocation it was made for, so each growth moves the blob.
+    let mut message = small_segment_message();
+    let mut st = message.init_root::<::any_pointer::Builder>().init_any_struct(0, 2);
+    {
+        let mut text: ::text::GrowableBuilder = st.borrow().get_pointer_field(0).init_as();
+        for _ in 0..1000 {
+            text.push('x').unwrap();
+        }
+        assert_eq!(text.len(), 1000);
+    }
+    {
+        let mut data: ::data::GrowableBuilder = st.borrow().get_pointer_field(1).init_as();
+        for i in 0..1000 {
+            data.push(i as u8).unwrap();
+        }
+        assert_eq!(data.len(), 1000);
+    }
+
+    let st: ::any_struct::Reader = message.get_root_as_reader().unwrap();
+    assert_eq!(st.get_pointer_field(0).get_as::<::text::Reader>().unwrap(), &*"x".repeat(1000));
+    assert_eq!(st.get_pointer_field(1).get_as::<::data::Reader>().unwrap().len(), 1000);
+
+    // Growing one byte at a time abandons no more than a few times the final size.
+    let words: usize = message.get_segments_for_output().iter().map(|segment| segment.len()).sum();
+    assert!(words < 4 * (1 + 2 + 126 + 125), "{} words", words);
+}
+
+#[test]
+fn struct_sections() {
+    let mut message = ::message::Builder::new_default();
+    {
+        let st = message.init_root::<::any_pointer::Builder>().init_any_struct(2, 3);
+        for (idx, mut pointer) in st.iter_pointers().enumerate() {
+            if idx != 1 {
+                pointer.set_as::<::text::Builder, _>(&*format!("field {}", idx)).unwrap();
+            }
+        }
+    }
+    {
+        let st: ::any_struct::Builder = message.get_root().unwrap();
+        let data = st.get_data_section();
+        assert_eq!(data.len(), 16);
+        data[15] = 0xff;
+    }
+
+    let root = message.get_root_as_reader::<::any_pointer::Reader>().unwrap();
+    let reader = root.get_as::<::any_struct::Reader>().unwrap();
+    assert_eq!(reader.get_data_section()[15], 0xff);
+    let texts: Vec<Option<::text::Reader>> = reader.iter_pointers()
+        .map(|p| if p.is_null() { None } else { Some(p.get_as().unwrap()) })
+        .collect();
+    assert_eq!(texts, vec![Some("field 0"), None, Some("field 2")]);
+
+    use traits::IntoInternalStructReader;
+    let raw = reader.into_internal_struct_reader();
+    assert_eq!(raw.iter_pointer_section().filter(|p| !p.is_null()).count(), 2);
+}
+
+#[test]
+fn struct_sections_of_list_elements() {
+    // [1u32, 2, 3]
+    let data: &[Word] = &[
+        capnp_word!(0x01, 0x00, 0x00, 0x00, 0x1c, 0x00, 0x00, 0x00),
+        capnp_word!(0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00),
+        capnp_word!(0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00)];
+    let list = ::private::layout::PointerReader::get_root_unchecked(data.as_ptr())
+        .get_list(::private::layout::ElementSize::InlineComposite, ::std::ptr::null())
+        .unwrap();
+    let element = list.get_struct_element(1);
+    assert_eq!(element.get_data_section_as_blob(), &[2, 0, 0, 0]);
+    assert_eq!(element.iter_pointer_section().count(), 0);
+
+    // The same bits as in bool_list().
+    let data: &[Word] = &[
+        capnp_word!(0x01, 0x00, 0x00, 0x00, 0x51, 0x00, 0x00, 0x00),
+        capnp_word!(0x75, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00)];
+    let list = ::private::layout::PointerReader::get_root_unchecked(data.as_ptr())
+        .get_list(::private::layout::ElementSize::InlineComposite, ::std::ptr::null())
+        .unwrap();
+    assert_eq!(list.get_struct_element(9).get_data_section_as_blob().len(), 0);
+
+    assert_eq!(::private::layout::StructReader::new_default().get_data_section_as_blob().len(), 0);
+}
+
+#[test]
+fn struct_builder_sections() {
+    let mut message = ::message::Builder::new_default();
+    {
+        let mut st = message.init_root::<::any_pointer::Builder>().init_any_struct(1, 2);
+        st.borrow().get_data_section()[0] = 7;
+        for (idx, mut pointer) in st.iter_pointers().enumerate() {
+            pointer.set_as::<::text::Builder, _>(if idx == 0 { "a" } else { "b" }).unwrap();
+        }
+    }
+    let st: ::any_struct::Reader = message.get_root_as_reader().unwrap();
+    assert_eq!(st.get_data_section(), &[7, 0, 0, 0, 0, 0, 0, 0]);
+    let texts: Vec<::text::Reader> = st.iter_pointers().map(|p| p.get_as().unwrap()).collect();
+    assert_eq!(texts, vec!["a", "b"]);
+}
@@ -36,6 +36,9 @@ mod zero;
 #[cfg(test)]
 mod layout_test;
 
+#[cfg(all(test, feature = "rpc"))]
+pub mod test_server;
+
 pub struct RawSchema<'a> {
     pub blob: &'a [Word],
 }
@@ -0,0 +1,85 @@
+// Copyright (c) 2013-2015 Sandstorm Development Group, Inc. and contributors
+// Licensed under the MIT License:
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! A capability server shared by the tests of the capability wrappers.
+
+use futures::Future;
+use futures::sync::oneshot;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use any_pointer;
+use capability::{Params, Promise, Results, Server};
+use primitive_list;
+use Error;
+
+/// The calls to method 2 that have not been completed yet, oldest first.
+pub type Pending = Rc<RefCell<Vec<oneshot::Sender<()>>>>;
+
+/// Method 0 doubles the number in element 0 of a `u64` list, method 1 fails, method 2 returns
+/// only once the test completes it, and method 3 returns a new `TestServer` in pointer 0 of a
+/// struct.
+pub struct TestServer {
+    pending: Pending,
+}
+
+impl Server for TestServer {
+    fn dispatch_call(&mut self, _interface_id: u64, method_id: u16,
+                     params: Params<any_pointer::Owned>,
+                     mut results: Results<any_pointer::Owned>) -> Promise<(), Error>
+    {
+        match method_id {
+            0 => {
+                let value = match params.get().and_then(|p| p.get_as::<primitive_list::Reader<u64>>()) {
+                    Ok(list) => list.get(0),
+                    Err(e) => return Promise::err(e),
+                };
+                results.get().initn_as::<primitive_list::Builder<u64>>(1).set(0, value * 2);
+                Promise::ok(())
+            }
+            1 => Promise::err(Error::unimplemented("method 1 always fails".to_string())),
+            2 => {
+                let (sender, receiver) = oneshot::channel();
+                self.pending.borrow_mut().push(sender);
+                Promise::from_future(receiver.map_err(Error::from))
+            }
+            _ => {
+                let cap = ::local::Client::new(Box::new(TestServer { pending: self.pending.clone() }));
+                results.get().init_any_struct(0, 1).get_pointer_field(0)
+                    .set_as_capability(Box::new(cap));
+                Promise::ok(())
+            }
+        }
+    }
+}
+
+/// Returns a client for a new `TestServer`, along with its pending calls.
+pub fn new_client() -> (::capability::Client, Pending) {
+    let pending = Rc::new(RefCell::new(Vec::new()));
+    let server = ::local::Client::new(Box::new(TestServer { pending: pending.clone() }));
+    (::capability::Client::new(Box::new(server)), pending)
+}
+
+/// Completes the oldest pending call.
+pub fn finish_first(pending: &Pending) {
+    let _ = pending.borrow_mut().remove(0).send(());
+}
@@ -0,0 +1,304 @@
+// Copyright (c) 2013-2015 Sandstorm Development Group, Inc. and contributors
+// Licensed under the MIT License:
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! A capability that stands in for one that is not known yet.
+
+use futures::{Async, Future, Poll};
+use futures::future::Shared;
+use futures::sync::oneshot;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use any_pointer;
+use capability::Promise;
+use private::capability::{ClientHook, ParamsHook, ResultsHook};
+use {Error, MessageSize};
+
+struct QueuedCall {
+    interface_id: u64,
+    method_id: u16,
+    params: Box<ParamsHook>,
+    results: Box<ResultsHook>,
+    forwarded: oneshot::Sender<Promise<(), Error>>,
+}
+
+enum State {
+    Waiting(Vec<QueuedCall>),
+    Resolved(Box<ClientHook>),
+    Broken(Error),
+}
+
+struct Inner {
+    resolution: Shared<Promise<Box<ClientHook>, Error>>,
+    state: RefCell<State>,
+}
+
+impl Inner {
+    /// Records the outcome of `resolution` and forwards the queued calls, in the order in
+    /// which they were made. Does nothing if that has already happened.
+    fn resolve(&self, resolution: Result<Box<ClientHook>, Error>) {
+        let queue = {
+            let mut state = self.state.borrow_mut();
+            if let State::Waiting(_) = *state {} else { return }
+            let next = match resolution {
+                Ok(ref cap) => State::Resolved(cap.add_ref()),
+                Err(ref e) => State::Broken(e.clone()),
+            };
+            match ::std::mem::replace(&mut *state, next) {
+                State::Waiting(queue) => queue,
+                _ => unreachable!(),
+            }
+        };
+
+        for call in queue {
+            let QueuedCall { interface_id, method_id, params, results, forwarded } = call;
+            let promise = match resolution {
+                Ok(ref cap) => cap.call(interface_id, method_id, params, results),
+                Err(ref e) => Promise::err(e.clone()),
+            };
+            // The caller may have given up on the call already.
+            let _ = forwarded.send(promise);
+        }
+    }
+
+    /// Checks whether the resolution has arrived, without waiting for it.
+    fn poll_resolution(&self) -> Poll<(), Error> {
+        let resolution = match self.resolution.clone().poll() {
+            Ok(Async::NotReady) => return Ok(Async::NotReady),
+            Ok(Async::Ready(cap)) => Ok(cap.add_ref()),
+            Err(e) => Err((*e).clone()),
+        };
+        self.resolve(resolution);
+        Ok(Async::Ready(()))
+    }
+
+    fn peek(&self) {
+        match self.resolution.peek() {
+            Some(Ok(cap)) => self.resolve(Ok(cap.add_ref())),
+            Some(Err(e)) => self.resolve(Err((*e).clone())),
+            None => (),
+        }
+    }
+}
+
+/// A call that was made before the resolution arrived.
+struct Forwarded {
+    inner: Rc<Inner>,
+    receiver: oneshot::Receiver<Promise<(), Error>>,
+    promise: Option<Promise<(), Error>>,
+}
+
+impl Future for Forwarded {
+    type Item = ();
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<(), Error> {
+        loop {
+            if let Some(ref mut promise) = self.promise {
+                return promise.poll();
+            }
+            match try!(self.receiver.poll()) {
+                Async::Ready(promise) => {
+                    self.promise = Some(promise);
+                    continue;
+                }
+                Async::NotReady => (),
+            }
+            // Any caller can drive the resolution; whoever sees it arrive forwards the queue.
+            if let Async::NotReady = try!(self.inner.poll_resolution()) {
+                return Ok(Async::NotReady);
+            }
+        }
+    }
+}
+
+/// A capability that is backed by a promise for another capability. Calls made before the
+/// promise resolves are queued, and are made on the capability it resolves to, in order, once it
+/// does. If the promise fails, so do all calls.
+pub struct Client {
+    inner: Rc<Inner>,
+}
+
+impl Client {
+    /// Returns a capability that stands in for the one that `resolution` yields. Nothing polls
+    /// `resolution` on its own; it is driven by polling the promises of calls made on the new
+    /// client, or of `when_more_resolved()`.
+    pub fn new(resolution: Promise<Box<ClientHook>, Error>) -> Client {
+        Client {
+            inner: Rc::new(Inner {
+                resolution: resolution.shared(),
+                state: RefCell::new(State::Waiting(Vec::new())),
+            }),
+        }
+    }
+}
+
+impl ClientHook for Client {
+    fn add_ref(&self) -> Box<ClientHook> {
+        Box::new(Client { inner: self.inner.clone() })
+    }
+
+    fn new_call(&self, interface_id: u64, method_id: u16, size_hint: Option<MessageSize>)
+                -> ::capability::Request<any_pointer::Owned, any_pointer::Owned>
+    {
+        ::local::new_forwarding_call(self.add_ref(), interface_id, method_id, size_hint)
+    }
+
+    fn call(&self, interface_id: u64, method_id: u16,
+            params: Box<ParamsHook>, results: Box<ResultsHook>) -> Promise<(), Error>
+    {
+        // Forward the queue first if the resolution has arrived, to keep calls in order.
+        self.inner.peek();
+        let cap = match *self.inner.state.borrow_mut() {
+            State::Resolved(ref cap) => cap.add_ref(),
+            State::Broken(ref e) => return Promise::err(e.clone()),
+            State::Waiting(ref mut queue) => {
+                let (sender, receiver) = oneshot::channel();
+                queue.push(QueuedCall {
+                    interface_id: interface_id,
+                    method_id: method_id,
+                    params: params,
+                    results: results,
+                    forwarded: sender,
+                });
+                return Promise::from_future(Forwarded {
+                    inner: self.inner.clone(),
+                    receiver: receiver,
+                    promise: None,
+                });
+            }
+        };
+        cap.call(interface_id, method_id, params, results)
+    }
+
+    fn get_brand(&self) -> usize {
+        0
+    }
+
+    fn get_ptr(&self) -> usize {
+        &*self.inner as *const _ as usize
+    }
+
+    fn get_resolved(&self) -> Option<Box<ClientHook>> {
+        self.inner.peek();
+        match *self.inner.state.borrow() {
+            State::Resolved(ref cap) => Some(cap.add_ref()),
+            State::Broken(ref e) => Some(::broken::new_cap(e.clone())),
+            State::Waiting(_) => None,
+        }
+    }
+
+    fn when_more_resolved(&self) -> Option<Promise<Box<ClientHook>, Error>> {
+        self.inner.peek();
+        match *self.inner.state.borrow() {
+            State::Resolved(ref cap) => return Some(Promise::ok(cap.add_ref())),
+            State::Broken(ref e) => return Some(Promise::err(e.clone())),
+            State::Waiting(_) => (),
+        }
+        let inner = self.inner.clone();
+        let resolution = self.inner.resolution.clone().then(move |resolution| {
+            let resolution = match resolution {
+                Ok(cap) => Ok(cap.add_ref()),
+                Err(e) => Err((*e).clone()),
+            };
+            inner.resolve(match resolution {
+                Ok(ref cap) => Ok(cap.add_ref()),
+                Err(ref e) => Err(e.clone()),
+            });
+            resolution
+        });
+        Some(Promise::from_future(resolution))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::Future;
+    use futures::sync::oneshot;
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use any_pointer;
+    use capability::{Params, Promise, Results, Server};
+    use private::capability::ClientHook;
+    use {Error, ErrorKind};
+
+    struct Recorder {
+        calls: Rc<RefCell<Vec<u16>>>,
+    }
+
+    impl Server for Recorder {
+        fn dispatch_call(&mut self, _interface_id: u64, method_id: u16,
+                         _params: Params<any_pointer::Owned>,
+                         _results: Results<any_pointer::Owned>) -> Promise<(), Error>
+        {
+            self.calls.borrow_mut().push(method_id);
+            Promise::ok(())
+        }
+    }
+
+    fn call(client: &ClientHook, method_id: u16) -> Promise<(), Error> {
+        let request = client.new_call(0, method_id, None);
+        Promise::from_future(request.hook.send().promise.map(|_| ()))
+    }
+
+    #[test]
+    fn queued_calls_are_forwarded_in_order() {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let (fulfiller, resolution) = oneshot::channel::<Box<ClientHook>>();
+        let client = super::Client::new(Promise::from_future(resolution.map_err(Error::from)));
+
+        let first = call(&client, 0);
+        let second = call(&client, 1);
+        let third = call(&client, 2);
+        assert!(client.get_resolved().is_none());
+
+        let target = ::local::Client::new(Box::new(Recorder { calls: calls.clone() }));
+        let target_ptr = target.get_ptr();
+        let _ = fulfiller.send(Box::new(target));
+
+        third.wait().unwrap();
+        assert_eq!(*calls.borrow(), vec![0, 1, 2]);
+        first.wait().unwrap();
+        second.wait().unwrap();
+
+        call(&client, 3).wait().unwrap();
+        assert_eq!(*calls.borrow(), vec![0, 1, 2, 3]);
+        assert_eq!(client.get_resolved().unwrap().get_ptr(), target_ptr);
+        assert_eq!(client.when_more_resolved().unwrap().wait().unwrap().get_ptr(), target_ptr);
+    }
+
+    #[test]
+    fn queued_calls_fail_if_resolution_fails() {
+        let (fulfiller, resolution) = oneshot::channel::<Box<ClientHook>>();
+        let client = super::Client::new(Promise::from_future(resolution.map_err(Error::from)));
+        let early = call(&client, 0);
+        let resolved = client.when_more_resolved().unwrap();
+        drop(fulfiller);
+
+        assert_eq!(early.wait().unwrap_err().kind, ErrorKind::Failed);
+        assert!(resolved.wait().is_err());
+        assert!(call(&client, 1).wait().is_err());
+        assert!(call(&*client.get_resolved().unwrap(), 2).wait().is_err());
+    }
+}
@@ -0,0 +1,230 @@
+// Copyright (c) 2013-2015 Sandstorm Development Group, Inc. and contributors
+// Licensed under the MIT License:
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Capabilities that can be cut off.
+//!
+//! `new()` wraps a capability in a forwarder and returns a `Revoker` for it. Once revoked, the
+//! forwarder fails every call, including calls that are still in flight. Capabilities returned
+//! by calls on the forwarder are wrapped too, so pipelined calls and calls on returned
+//! capabilities are cut off along with it.
+
+use futures::Future;
+use futures::sync::oneshot;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use any_pointer;
+use capability::Promise;
+use private::capability::{ClientHook, ParamsHook, ResultsHook, SharedHook};
+use {Error, MessageSize};
+
+struct Inner {
+    revoked: RefCell<Option<Error>>,
+
+    // One for each call that is in flight, to be told about the revocation.
+    in_flight: RefCell<Vec<oneshot::Sender<()>>>,
+}
+
+impl Inner {
+    fn revocation(&self) -> Option<Error> {
+        self.revoked.borrow().clone()
+    }
+
+    /// Returns a promise that fails with the revocation error once `revoke()` is called.
+    fn when_revoked<T: 'static>(inner: &Rc<Inner>) -> Promise<T, Error> {
+        let (sender, receiver) = oneshot::channel();
+        {
+            let mut in_flight = inner.in_flight.borrow_mut();
+            in_flight.retain(|sender| !sender.is_canceled());
+            in_flight.push(sender);
+        }
+        let inner = inner.clone();
+        Promise::from_future(receiver.then(move |_| {
+            Err(inner.revocation().expect("revocable capability was not revoked"))
+        }))
+    }
+}
+
+/// Cuts off the forwarder that was returned along with it by `new()`.
+pub struct Revoker {
+    inner: Rc<Inner>,
+}
+
+impl Revoker {
+    /// Fails all future and in-flight calls on the forwarder with `error`. Only the first
+    /// revocation has an effect.
+    pub fn revoke(&self, error: Error) {
+        if self.inner.revoked.borrow().is_some() {
+            return;
+        }
+        *self.inner.revoked.borrow_mut() = Some(error);
+        let in_flight = ::std::mem::take(&mut *self.inner.in_flight.borrow_mut());
+        for sender in in_flight {
+            let _ = sender.send(());
+        }
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.inner.revoked.borrow().is_some()
+    }
+}
+
+/// Wraps `client` in a forwarder that can be revoked through the returned `Revoker`.
+pub fn new(client: ::capability::Client) -> (::capability::Client, Revoker) {
+    let inner = Rc::new(Inner {
+        revoked: RefCell::new(None),
+        in_flight: RefCell::new(Vec::new()),
+    });
+    let forwarder = Client { target: SharedHook::new(client.hook), inner: inner.clone() };
+    (::capability::Client::new(Box::new(forwarder)), Revoker { inner: inner })
+}
+
+fn wrap(cap: Box<ClientHook>, inner: &Rc<Inner>) -> Box<ClientHook> {
+    if cap.is_null() {
+        cap
+    } else {
+        Box::new(Client { target: SharedHook::new(cap), inner: inner.clone() })
+    }
+}
+
+struct Client {
+    target: SharedHook,
+    inner: Rc<Inner>,
+}
+
+impl ClientHook for Client {
+    fn add_ref(&self) -> Box<ClientHook> {
+        Box::new(Client { target: self.target.clone(), inner: self.inner.clone() })
+    }
+
+    fn new_call(&self, interface_id: u64, method_id: u16, size_hint: Option<MessageSize>)
+                -> ::capability::Request<any_pointer::Owned, any_pointer::Owned>
+    {
+        ::local::new_forwarding_call(self.add_ref(), interface_id, method_id, size_hint)
+    }
+
+    fn call(&self, interface_id: u64, method_id: u16,
+            params: Box<ParamsHook>, mut results: Box<ResultsHook>) -> Promise<(), Error>
+    {
+        if let Some(e) = self.inner.revocation() {
+            return Promise::err(e);
+        }
+
+        let mut request = self.target.new_call(interface_id, method_id, None);
+        if let Err(e) = params.get().and_then(|params| request.hook.get().set_as(params)) {
+            return Promise::err(e);
+        }
+        drop(params);
+
+        let inner = self.inner.clone();
+        let call = request.hook.send().promise.and_then(move |response| {
+            let builder = try!(results.get());
+            builder.set_with_cap_translator(try!(response.hook.get()), |cap| {
+                Ok(Some(wrap(cap, &inner)))
+            })
+        });
+        let call = call.select(Inner::when_revoked(&self.inner))
+            .map(|((), _)| ())
+            .map_err(|(e, _)| e);
+        Promise::from_future(call)
+    }
+
+    fn get_brand(&self) -> usize {
+        0
+    }
+
+    fn get_ptr(&self) -> usize {
+        self.target.identity()
+    }
+
+    fn get_resolved(&self) -> Option<Box<ClientHook>> {
+        self.target.get_resolved().map(|cap| wrap(cap, &self.inner))
+    }
+
+    fn when_more_resolved(&self) -> Option<Promise<Box<ClientHook>, Error>> {
+        if let Some(e) = self.inner.revocation() {
+            return Some(Promise::err(e));
+        }
+        let inner = self.inner.clone();
+        self.target.when_more_resolved().map(|promise| {
+            let resolution = promise.map(move |cap| wrap(cap, &inner))
+                .select(Inner::when_revoked(&self.inner))
+                .map(|(cap, _)| cap)
+                .map_err(|(e, _)| e);
+            Promise::from_future(resolution)
+        })
+    }
+
+    fn is_null(&self) -> bool {
+        self.target.is_null()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::Future;
+
+    use primitive_list;
+    use private::capability::ClientHook;
+    use private::test_server;
+    use {Error, ErrorKind, Result};
+
+    fn double(client: &ClientHook, value: u64) -> Result<u64> {
+        let mut request = client.new_call(0, 0, None);
+        request.get().initn_as::<primitive_list::Builder<u64>>(1).set(0, value);
+        let response = try!(request.hook.send().promise.wait());
+        let list: primitive_list::Reader<u64> = try!(try!(response.get()).get_as());
+        Ok(list.get(0))
+    }
+
+    #[test]
+    fn revoke() {
+        let (plugin, _pending) = test_server::new_client();
+        let plugin_ptr = plugin.hook.get_ptr();
+        let (client, revoker) = super::new(plugin);
+        assert!(client.hook.get_ptr() != plugin_ptr);
+        assert_eq!(client.hook.add_ref().get_ptr(), client.hook.get_ptr());
+        assert_eq!(double(&*client.hook, 4).unwrap(), 8);
+
+        let in_flight = client.hook.new_call(0, 2, None).hook.send().promise;
+
+        let response = client.hook.new_call(0, 3, None).hook.send().promise.wait().unwrap();
+        let st: ::any_struct::Reader = response.get().unwrap().get_as().unwrap();
+        let returned = st.get_pointer_field(0).get_pipelined_cap(&[]).unwrap();
+        assert_eq!(double(&*returned, 5).unwrap(), 10);
+        assert_eq!(returned.add_ref().get_ptr(), returned.get_ptr());
+        assert!(returned.get_ptr() != client.hook.get_ptr());
+
+        let pipelined = client.hook.new_call(0, 3, None).hook.send()
+            .pipeline.get_pointer_field(0).as_cap();
+
+        assert!(!revoker.is_revoked());
+        revoker.revoke(Error::disconnected("plugin was unloaded".to_string()));
+        assert!(revoker.is_revoked());
+
+        assert_eq!(in_flight.wait().err().unwrap().kind, ErrorKind::Disconnected);
+        assert_eq!(double(&*client.hook, 1).unwrap_err().kind, ErrorKind::Disconnected);
+        assert_eq!(double(&*returned, 1).unwrap_err().kind, ErrorKind::Disconnected);
+        assert_eq!(double(&*pipelined, 1).unwrap_err().kind, ErrorKind::Disconnected);
+        assert_eq!(client.when_resolved().wait().unwrap_err().kind, ErrorKind::Disconnected);
+    }
+}
@@ -0,0 +1,860 @@
+// Copyright (c) 2013-2017 Sandstorm Development Group, Inc. and contributors
+// Licensed under the MIT License:
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Detection of wire-incompatible changes between two versions of a schema.
+//!
+//! Schemas are loaded at runtime from the `Node`s of a `CodeGeneratorRequest`, such as the one
+//! written by `capnp compile -o- foo.capnp`. Nodes are matched up by id, and fields, enumerants
+//! and methods by their position in the node, which (unlike their names) cannot change as a
+//! schema evolves. See the
+//! [schema evolution rules](https://capnproto.org/language.html#evolving-your-protocol)
+//! for what is allowed.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ptr;
+
+use any_pointer;
+use message;
+use private::layout::{ElementSize, ListReader, PointerReader, StructReader};
+use traits::FromPointerReader;
+use {Error, Result};
+
+// Layout of the structs from schema.capnp that we need to look at.
+
+const NODE_ID: usize = 0;                       // UInt64, data word 0
+const NODE_WHICH: usize = 6;                    // UInt16
+const NODE_DISPLAY_NAME: usize = 0;             // pointer
+const NODE_STRUCT: u16 = 1;
+const NODE_ENUM: u16 = 2;
+const NODE_INTERFACE: u16 = 3;
+const STRUCT_DATA_WORD_COUNT: usize = 7;        // UInt16
+const STRUCT_POINTER_COUNT: usize = 12;         // UInt16
+const STRUCT_IS_GROUP: usize = 224;             // Bool
+const STRUCT_DISCRIMINANT_COUNT: usize = 15;    // UInt16
+const STRUCT_DISCRIMINANT_OFFSET: usize = 8;    // UInt32
+const NODE_MEMBERS: usize = 3;                  // pointer: fields, enumerants or methods
+
+const FIELD_NAME: usize = 0;                    // pointer
+const FIELD_DISCRIMINANT_VALUE: usize = 1;      // UInt16, default 0xffff
+const FIELD_WHICH: usize = 4;                   // UInt16
+const FIELD_SLOT: u16 = 0;
+const FIELD_GROUP: u16 = 1;
+const SLOT_OFFSET: usize = 1;                   // UInt32
+const SLOT_TYPE: usize = 2;                     // pointer
+const SLOT_DEFAULT_VALUE: usize = 3;            // pointer
+const GROUP_TYPE_ID: usize = 2;                 // UInt64
+const FIELD_ORDINAL_WHICH: usize = 5;           // UInt16
+const FIELD_ORDINAL_EXPLICIT: usize = 6;        // UInt16
+
+const ENUMERANT_NAME: usize = 0;                // pointer
+
+const METHOD_NAME: usize = 0;                   // pointer
+const METHOD_PARAM_STRUCT_TYPE: usize = 1;      // UInt64
+const METHOD_RESULT_STRUCT_TYPE: usize = 2;     // UInt64
+
+const TYPE_WHICH: usize = 0;                    // UInt16
+const TYPE_LIST_ELEMENT_TYPE: usize = 0;        // pointer
+const TYPE_ID: usize = 1;                       // UInt64, for enum, struct and interface
+
+const VALUE_WHICH: usize = 0;                   // UInt16
+const VALUE_POINTER: usize = 0;                 // pointer
+
+const NO_DISCRIMINANT: u16 = 0xffff;
+
+/// A list of structs, read without knowing the struct type.
+struct StructList<'a>(ListReader<'a>);
+
+impl <'a> FromPointerReader<'a> for StructList<'a> {
+    fn get_from_pointer(reader: &PointerReader<'a>) -> Result<StructList<'a>> {
+        Ok(StructList(try!(reader.get_list(ElementSize::InlineComposite, ptr::null()))))
+    }
+}
+
+impl <'a> StructList<'a> {
+    fn len(&self) -> u32 { self.0.len() }
+    fn get(&self, index: u32) -> StructReader<'a> { self.0.get_struct_element(index) }
+}
+
+/// A struct, read without knowing its type.
+struct Struct<'a>(StructReader<'a>);
+
+impl <'a> FromPointerReader<'a> for Struct<'a> {
+    fn get_from_pointer(reader: &PointerReader<'a>) -> Result<Struct<'a>> {
+        Ok(Struct(try!(reader.get_struct(ptr::null()))))
+    }
+}
+
+fn get_struct_list<'a>(reader: &StructReader<'a>, index: usize) -> Result<StructList<'a>> {
+    StructList::get_from_pointer(&reader.get_pointer_field(index))
+}
+
+fn get_name<'a>(reader: &StructReader<'a>, index: usize) -> Result<&'a str> {
+    if reader.get_pointer_field(index).is_null() {
+        Ok("")
+    } else {
+        reader.get_pointer_field(index).get_text(ptr::null(), 0)
+    }
+}
+
+/// A set of schema nodes, indexed by id.
+pub struct Schema<'a> {
+    nodes: HashMap<u64, StructReader<'a>>,
+}
+
+impl <'a> Schema<'a> {
+    /// Loads the nodes of a `CodeGeneratorRequest`.
+    pub fn from_code_generator_request(request: any_pointer::Reader<'a>) -> Result<Schema<'a>> {
+        let Struct(request) = try!(request.get_as());
+        Schema::from_node_list(try!(get_struct_list(&request, 0)))
+    }
+
+    /// Loads a `List(Node)`.
+    pub fn from_nodes(nodes: any_pointer::Reader<'a>) -> Result<Schema<'a>> {
+        Schema::from_node_list(try!(nodes.get_as()))
+    }
+
+    fn from_node_list(nodes: StructList<'a>) -> Result<Schema<'a>> {
+        let mut result = HashMap::new();
+        for idx in 0..nodes.len() {
+            let node = nodes.get(idx);
+            result.insert(node.get_data_field::<u64>(NODE_ID), node);
+        }
+        Ok(Schema { nodes: result })
+    }
+
+    /// Returns the number of nodes in the schema.
+    pub fn len(&self) -> usize { self.nodes.len() }
+
+    pub fn is_empty(&self) -> bool { self.nodes.is_empty() }
+
+    fn display_name(&self, id: u64) -> String {
+        match self.nodes.get(&id).map(|node| get_name(node, NODE_DISPLAY_NAME)) {
+            Some(Ok(name)) if !name.is_empty() => name.to_string(),
+            _ => format!("@0x{:016x}", id),
+        }
+    }
+}
+
+/// What is wrong with a change.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IncompatibilityKind {
+    /// A struct became an enum, an enum became an interface, etc.
+    NodeKindChanged,
+
+    /// The data or pointer section of a struct got smaller.
+    StructShrunk { old_data_words: u16, new_data_words: u16,
+                   old_pointer_count: u16, new_pointer_count: u16 },
+
+    /// A field that existed in the old schema does not exist in the new one.
+    FieldRemoved,
+
+    /// The field at this position now has a different ordinal.
+    OrdinalChanged { old: String, new: String },
+
+    /// The field now has a different type.
+    FieldTypeChanged { old: String, new: String },
+
+    /// The field now lives at a different location in the struct.
+    FieldOffsetChanged { old: u32, new: u32 },
+
+    /// A slot turned into a group, or the other way around.
+    FieldKindChanged,
+
+    /// The field has a different default value.
+    DefaultValueChanged,
+
+    /// The field has moved into, out of, or within a union, or the union's discriminant moved.
+    UnionMembershipChanged,
+
+    /// An enumerant that existed in the old schema does not exist in the new one.
+    EnumerantRemoved,
+
+    /// A method that existed in the old schema does not exist in the new one.
+    MethodRemoved,
+
+    /// The method's parameter or result type changed.
+    MethodSignatureChanged { old: String, new: String },
+}
+
+/// A change from an old schema to a new one that can break communication between programs
+/// that use the different versions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Incompatibility {
+    /// The id of the node in which the change was found.
+    pub node_id: u64,
+
+    /// Human-readable name of the changed element, such as `foo.capnp:Foo.bar`.
+    pub path: String,
+
+    pub kind: IncompatibilityKind,
+}
+
+impl fmt::Display for Incompatibility {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(fmt, "{}: ", self.path));
+        match self.kind {
+            IncompatibilityKind::NodeKindChanged => write!(fmt, "kind of declaration changed"),
+            IncompatibilityKind::StructShrunk { old_data_words, new_data_words,
+                                                old_pointer_count, new_pointer_count } =>
+                write!(fmt, "struct shrunk from {} data words and {} pointers to {} data words \
+                             and {} pointers",
+                       old_data_words, old_pointer_count, new_data_words, new_pointer_count),
+            IncompatibilityKind::FieldRemoved => write!(fmt, "field removed"),
+            IncompatibilityKind::OrdinalChanged { ref old, ref new } =>
+                write!(fmt, "ordinal changed from {} to {}", old, new),
+            IncompatibilityKind::FieldTypeChanged { ref old, ref new } =>
+                write!(fmt, "type changed from {} to {}", old, new),
+            IncompatibilityKind::FieldOffsetChanged { old, new } =>
+                write!(fmt, "offset changed from {} to {}", old, new),
+            IncompatibilityKind::FieldKindChanged =>
+                write!(fmt, "changed between a group and a regular field"),
+            IncompatibilityKind::DefaultValueChanged => write!(fmt, "default value changed"),
+            IncompatibilityKind::UnionMembershipChanged => write!(fmt, "union membership changed"),
+            IncompatibilityKind::EnumerantRemoved => write!(fmt, "enumerant removed"),
+            IncompatibilityKind::MethodRemoved => write!(fmt, "method removed"),
+            IncompatibilityKind::MethodSignatureChanged { ref old, ref new } =>
+                write!(fmt, "signature changed from {} to {}", old, new),
+        }
+    }
+}
+
+/// A decoded `schema.capnp` `Type`.
+#[derive(Clone, PartialEq)]
+enum Type {
+    Void, Bool, Int8, Int16, Int32, Int64, UInt8, UInt16, UInt32, UInt64, Float32, Float64,
+    Text, Data,
+    List(Box<Type>),
+    Enum(u64),
+    Struct(u64),
+    Interface(u64),
+    AnyPointer,
+}
+
+impl Type {
+    fn read(reader: StructReader) -> Result<Type> {
+        Ok(match reader.get_data_field::<u16>(TYPE_WHICH) {
+            0 => Type::Void,
+            1 => Type::Bool,
+            2 => Type::Int8,
+            3 => Type::Int16,
+            4 => Type::Int32,
+            5 => Type::Int64,
+            6 => Type::UInt8,
+            7 => Type::UInt16,
+            8 => Type::UInt32,
+            9 => Type::UInt64,
+            10 => Type::Float32,
+            11 => Type::Float64,
+            12 => Type::Text,
+            13 => Type::Data,
+            14 => {
+                let element_type = try!(reader.get_pointer_field(TYPE_LIST_ELEMENT_TYPE)
+                                              .get_struct(ptr::null()));
+                Type::List(Box::new(try!(Type::read(element_type))))
+            }
+            15 => Type::Enum(reader.get_data_field::<u64>(TYPE_ID)),
+            16 => Type::Struct(reader.get_data_field::<u64>(TYPE_ID)),
+            17 => Type::Interface(reader.get_data_field::<u64>(TYPE_ID)),
+            18 => Type::AnyPointer,
+            n => return Err(Error::failed(format!("Unknown type in schema: {}", n))),
+        })
+    }
+
+    fn is_pointer(&self) -> bool {
+        matches!(*self, Type::Text | Type::Data | Type::List(_) | Type::Struct(_) |
+                        Type::Interface(_) | Type::AnyPointer)
+    }
+
+    fn describe(&self, schema: &Schema) -> String {
+        match *self {
+            Type::Void => "Void".to_string(),
+            Type::Bool => "Bool".to_string(),
+            Type::Int8 => "Int8".to_string(),
+            Type::Int16 => "Int16".to_string(),
+            Type::Int32 => "Int32".to_string(),
+            Type::Int64 => "Int64".to_string(),
+            Type::UInt8 => "UInt8".to_string(),
+            Type::UInt16 => "UInt16".to_string(),
+            Type::UInt32 => "UInt32".to_string(),
+            Type::UInt64 => "UInt64".to_string(),
+            Type::Float32 => "Float32".to_string(),
+            Type::Float64 => "Float64".to_string(),
+            Type::Text => "Text".to_string(),
+            Type::Data => "Data".to_string(),
+            Type::List(ref element_type) => format!("List({})", element_type.describe(schema)),
+            Type::Enum(id) | Type::Struct(id) | Type::Interface(id) => schema.display_name(id),
+            Type::AnyPointer => "AnyPointer".to_string(),
+        }
+    }
+}
+
+/// A decoded `schema.capnp` `Value`. Pointer values are kept in their encoded form.
+enum Value<'a> {
+    Scalar(u16, u64),
+    Pointer(u16, any_pointer::Reader<'a>),
+}
+
+impl <'a> Value<'a> {
+    fn read(reader: StructReader<'a>) -> Value<'a> {
+        let which = reader.get_data_field::<u16>(VALUE_WHICH);
+        match which {
+            1 => Value::Scalar(which, reader.get_bool_field(16) as u64),
+            2 | 6 => Value::Scalar(which, reader.get_data_field::<u8>(2) as u64),
+            3 | 7 | 15 => Value::Scalar(which, reader.get_data_field::<u16>(1) as u64),
+            4 | 8 | 10 => Value::Scalar(which, reader.get_data_field::<u32>(1) as u64),
+            5 | 9 | 11 => Value::Scalar(which, reader.get_data_field::<u64>(1)),
+            12 | 13 | 14 | 16 | 18 =>
+                Value::Pointer(which, any_pointer::Reader::new(reader.get_pointer_field(VALUE_POINTER))),
+            _ => Value::Scalar(which, 0),
+        }
+    }
+
+    fn same_as(&self, other: &Value) -> Result<bool> {
+        match (self, other) {
+            (&Value::Scalar(w1, v1), &Value::Scalar(w2, v2)) => Ok(w1 == w2 && v1 == v2),
+            (&Value::Pointer(w1, p1), &Value::Pointer(w2, p2)) => {
+                if w1 != w2 { return Ok(false) }
+                if p1.is_null() || p2.is_null() { return Ok(p1.is_null() == p2.is_null()) }
+                Ok(try!(canonicalize(p1)) == try!(canonicalize(p2)))
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+/// Copies a value into a fresh message, so that equal values compare equal regardless of
+/// how they were laid out originally.
+fn canonicalize(value: any_pointer::Reader) -> Result<Vec<::Word>> {
+    let mut message = message::Builder::new_default();
+    try!(message.set_root(value));
+    let segments = message.get_segments_for_output();
+    Ok(segments.iter().flat_map(|segment| segment.iter().cloned()).collect())
+}
+
+/// Returns true if a field of type `old` can be read as a field of type `new`.
+fn is_compatible_type_change(old: &Type, new: &Type, new_schema: &Schema) -> Result<bool> {
+    if old == new {
+        return Ok(true)
+    }
+    match (old, new) {
+        // Text is Data with a NUL terminator.
+        (&Type::Text, &Type::Data) | (&Type::Data, &Type::Text) => Ok(true),
+
+        // Any pointer field can become AnyPointer.
+        (_, &Type::AnyPointer) if old.is_pointer() => Ok(true),
+
+        (Type::List(old_element), Type::List(new_element)) => {
+            match **new_element {
+                // A List(T) can be upgraded to a List(S), where S is a struct whose @0 field has
+                // type T.
+                Type::Struct(id) if !is_struct(old_element) => {
+                    match new_schema.nodes.get(&id) {
+                        None => Ok(false),
+                        Some(node) => {
+                            let fields = try!(get_struct_list(node, NODE_MEMBERS));
+                            if fields.len() == 0 { return Ok(false) }
+                            let field = fields.get(0);
+                            if field.get_data_field::<u16>(FIELD_WHICH) != FIELD_SLOT ||
+                                field.get_data_field::<u32>(SLOT_OFFSET) != 0
+                            {
+                                return Ok(false)
+                            }
+                            let first_type = try!(Type::read(try!(
+                                field.get_pointer_field(SLOT_TYPE).get_struct(ptr::null()))));
+                            Ok(first_type == **old_element)
+                        }
+                    }
+                }
+                _ => is_compatible_type_change(old_element, new_element, new_schema),
+            }
+        }
+        _ => Ok(false),
+    }
+}
+
+fn is_struct(t: &Type) -> bool {
+    matches!(*t, Type::Struct(_))
+}
+
+struct Checker<'a, 'b: 'a> {
+    old: &'a Schema<'b>,
+    new: &'a Schema<'b>,
+    result: Vec<Incompatibility>,
+}
+
+impl <'a, 'b> Checker<'a, 'b> {
+    fn report(&mut self, node_id: u64, path: String, kind: IncompatibilityKind) {
+        self.result.push(Incompatibility { node_id: node_id, path: path, kind: kind });
+    }
+
+    fn check_node(&mut self, id: u64, old: StructReader<'b>, new: StructReader<'b>) -> Result<()> {
+        let which = old.get_data_field::<u16>(NODE_WHICH);
+        if which != new.get_data_field::<u16>(NODE_WHICH) {
+            let path = self.old.display_name(id);
+            self.report(id, path, IncompatibilityKind::NodeKindChanged);
+            return Ok(())
+        }
+        match which {
+            NODE_STRUCT => self.check_struct(id, old, new),
+            NODE_ENUM => self.check_enum(id, old, new),
+            NODE_INTERFACE => self.check_interface(id, old, new),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_struct(&mut self, id: u64, old: StructReader<'b>, new: StructReader<'b>) -> Result<()> {
+        let path = self.old.display_name(id);
+        if !old.get_bool_field(STRUCT_IS_GROUP) {
+            let old_data_words = old.get_data_field::<u16>(STRUCT_DATA_WORD_COUNT);
+            let new_data_words = new.get_data_field::<u16>(STRUCT_DATA_WORD_COUNT);
+            let old_pointer_count = old.get_data_field::<u16>(STRUCT_POINTER_COUNT);
+            let new_pointer_count = new.get_data_field::<u16>(STRUCT_POINTER_COUNT);
+            if new_data_words < old_data_words || new_pointer_count < old_pointer_count {
+                self.report(id, path.clone(), IncompatibilityKind::StructShrunk {
+                    old_data_words: old_data_words, new_data_words: new_data_words,
+                    old_pointer_count: old_pointer_count, new_pointer_count: new_pointer_count,
+                });
+            }
+        }
+
+        // A union may be added around exactly one existing field.
+        let old_has_union = old.get_data_field::<u16>(STRUCT_DISCRIMINANT_COUNT) > 0;
+        if old_has_union &&
+            old.get_data_field::<u32>(STRUCT_DISCRIMINANT_OFFSET) !=
+            new.get_data_field::<u32>(STRUCT_DISCRIMINANT_OFFSET)
+        {
+            self.report(id, path.clone(), IncompatibilityKind::UnionMembershipChanged);
+        }
+        let mut moved_into_new_union = 0;
+
+        let old_fields = try!(get_struct_list(&old, NODE_MEMBERS));
+        let new_fields = try!(get_struct_list(&new, NODE_MEMBERS));
+
+        for idx in 0..old_fields.len() {
+            let old_field = old_fields.get(idx);
+            let name = try!(get_name(&old_field, FIELD_NAME));
+            let field_path = format!("{}.{}", path, name);
+            if idx >= new_fields.len() {
+                self.report(id, field_path, IncompatibilityKind::FieldRemoved);
+                continue;
+            }
+            let new_field = new_fields.get(idx);
+
+            let old_ordinal = describe_ordinal(&old_field, idx);
+            let new_ordinal = describe_ordinal(&new_field, idx);
+            if old_ordinal != new_ordinal {
+                self.report(id, field_path.clone(), IncompatibilityKind::OrdinalChanged {
+                    old: old_ordinal, new: new_ordinal,
+                });
+            }
+
+            let old_discriminant =
+                old_field.get_data_field_mask::<u16>(FIELD_DISCRIMINANT_VALUE, NO_DISCRIMINANT);
+            let new_discriminant =
+                new_field.get_data_field_mask::<u16>(FIELD_DISCRIMINANT_VALUE, NO_DISCRIMINANT);
+            if old_discriminant != new_discriminant {
+                if !old_has_union && old_discriminant == NO_DISCRIMINANT {
+                    moved_into_new_union += 1;
+                    if moved_into_new_union > 1 {
+                        self.report(id, field_path.clone(),
+                                    IncompatibilityKind::UnionMembershipChanged);
+                    }
+                } else {
+                    self.report(id, field_path.clone(),
+                                IncompatibilityKind::UnionMembershipChanged);
+                }
+            }
+
+            let old_which = old_field.get_data_field::<u16>(FIELD_WHICH);
+            if old_which != new_field.get_data_field::<u16>(FIELD_WHICH) {
+                self.report(id, field_path, IncompatibilityKind::FieldKindChanged);
+                continue;
+            }
+            match old_which {
+                FIELD_SLOT => try!(self.check_slot(id, field_path, old_field, new_field)),
+                FIELD_GROUP => {
+                    let old_group = old_field.get_data_field::<u64>(GROUP_TYPE_ID);
+                    let new_group = new_field.get_data_field::<u64>(GROUP_TYPE_ID);
+                    // Groups with matching ids are checked as nodes in their own right.
+                    if old_group != new_group {
+                        if let (Some(&old_node), Some(&new_node)) =
+                            (self.old.nodes.get(&old_group), self.new.nodes.get(&new_group))
+                        {
+                            try!(self.check_struct(old_group, old_node, new_node));
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn check_slot(&mut self, id: u64, path: String,
+                  old: StructReader<'b>, new: StructReader<'b>) -> Result<()> {
+        let old_type = try!(Type::read(try!(old.get_pointer_field(SLOT_TYPE).get_struct(ptr::null()))));
+        let new_type = try!(Type::read(try!(new.get_pointer_field(SLOT_TYPE).get_struct(ptr::null()))));
+        if !try!(is_compatible_type_change(&old_type, &new_type, self.new)) {
+            let kind = IncompatibilityKind::FieldTypeChanged {
+                old: old_type.describe(self.old),
+                new: new_type.describe(self.new),
+            };
+            self.report(id, path, kind);
+            return Ok(())
+        }
+
+        let old_offset = old.get_data_field::<u32>(SLOT_OFFSET);
+        let new_offset = new.get_data_field::<u32>(SLOT_OFFSET);
+        if old_offset != new_offset && old_type != Type::Void {
+            self.report(id, path.clone(),
+                        IncompatibilityKind::FieldOffsetChanged { old: old_offset, new: new_offset });
+        }
+
+        if old_type == new_type {
+            let old_default = Value::read(try!(old.get_pointer_field(SLOT_DEFAULT_VALUE)
+                                                  .get_struct(ptr::null())));
+            let new_default = Value::read(try!(new.get_pointer_field(SLOT_DEFAULT_VALUE)
+                                                  .get_struct(ptr::null())));
+            if !try!(old_default.same_as(&new_default)) {
+                self.report(id, path, IncompatibilityKind::DefaultValueChanged);
+            }
+        }
+        Ok(())
+    }
+
+    fn check_enum(&mut self, id: u64, old: StructReader<'b>, new: StructReader<'b>) -> Result<()> {
+        let path = self.old.display_name(id);
+        let old_enumerants = try!(get_struct_list(&old, NODE_MEMBERS));
+        let new_enumerants = try!(get_struct_list(&new, NODE_MEMBERS));
+        for idx in new_enumerants.len()..old_enumerants.len() {
+            let name = try!(get_name(&old_enumerants.get(idx), ENUMERANT_NAME));
+            self.report(id, format!("{}.{}", path, name), IncompatibilityKind::EnumerantRemoved);
+        }
+        Ok(())
+    }
+
+    fn check_interface(&mut self, id: u64, old: StructReader<'b>, new: StructReader<'b>) -> Result<()> {
+        let path = self.old.display_name(id);
+        let old_methods = try!(get_struct_list(&old, NODE_MEMBERS));
+        let new_methods = try!(get_struct_list(&new, NODE_MEMBERS));
+        for idx in 0..old_methods.len() {
+            let old_method = old_methods.get(idx);
+            let method_path = format!("{}.{}", path, try!(get_name(&old_method, METHOD_NAME)));
+            if idx >= new_methods.len() {
+                self.report(id, method_path, IncompatibilityKind::MethodRemoved);
+                continue;
+            }
+            let new_method = new_methods.get(idx);
+            let old_params = old_method.get_data_field::<u64>(METHOD_PARAM_STRUCT_TYPE);
+            let new_params = new_method.get_data_field::<u64>(METHOD_PARAM_STRUCT_TYPE);
+            let old_results = old_method.get_data_field::<u64>(METHOD_RESULT_STRUCT_TYPE);
+            let new_results = new_method.get_data_field::<u64>(METHOD_RESULT_STRUCT_TYPE);
+            if old_params != new_params || old_results != new_results {
+                let kind = IncompatibilityKind::MethodSignatureChanged {
+                    old: format!("{} -> {}", self.old.display_name(old_params),
+                                 self.old.display_name(old_results)),
+                    new: format!("{} -> {}", self.new.display_name(new_params),
+                                 self.new.display_name(new_results)),
+                };
+                self.report(id, method_path, kind);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn describe_ordinal(field: &StructReader, index: u32) -> String {
+    if field.get_data_field::<u16>(FIELD_ORDINAL_WHICH) == 1 {
+        format!("@{}", field.get_data_field::<u16>(FIELD_ORDINAL_EXPLICIT))
+    } else {
+        format!("implicit (position {})", index)
+    }
+}
+
+/// Compares two versions of a schema, returning the changes from `old` to `new` that are not
+/// wire compatible. Nodes that only appear in one of the schemas are not reported; any use
+/// of them from a field or method shows up as a type change.
+pub fn check<'a>(old: &Schema<'a>, new: &Schema<'a>) -> Result<Vec<Incompatibility>> {
+    let mut checker = Checker { old: old, new: new, result: Vec::new() };
+    let mut ids: Vec<u64> = old.nodes.keys().cloned().collect();
+    ids.sort();
+    for id in ids {
+        if let Some(&new_node) = new.nodes.get(&id) {
+            try!(checker.check_node(id, old.nodes[&id], new_node));
+        }
+    }
+    Ok(checker.result)
+}
+
+#[cfg(test)]
+mod tests {
+    use any_pointer;
+    use message;
+    use private::layout::{PointerBuilder, StructBuilder, StructSize};
+    use traits::FromPointerBuilder;
+    use Result;
+    use super::{check, Incompatibility, IncompatibilityKind, Schema};
+
+    struct Root<'a>(PointerBuilder<'a>);
+
+    impl <'a> FromPointerBuilder<'a> for Root<'a> {
+        fn init_pointer(builder: PointerBuilder<'a>, _size: u32) -> Root<'a> { Root(builder) }
+        fn get_from_pointer(builder: PointerBuilder<'a>) -> Result<Root<'a>> { Ok(Root(builder)) }
+    }
+
+    const UINT32: u16 = 8;
+    const UINT64: u16 = 9;
+    const TEXT: u16 = 12;
+    const DATA: u16 = 13;
+
+    #[derive(Clone)]
+    struct Field {
+        name: &'static str, type_which: u16, offset: u32, discriminant: u16, default: u32,
+        ordinal: Option<u16>,
+    }
+
+    fn field(name: &'static str, type_which: u16, offset: u32) -> Field {
+        Field { name: name, type_which: type_which, offset: offset, discriminant: 0xffff, default: 0,
+                ordinal: None }
+    }
+
+    enum Node {
+        Struct(u64, &'static str, u16, u16, Vec<Field>),
+        Enum(u64, &'static str, Vec<&'static str>),
+        Interface(u64, &'static str, Vec<(&'static str, u64, u64)>),
+    }
+
+    fn init_node(node: &StructBuilder, id: u64, name: &str, which: u16) {
+        node.set_data_field::<u64>(0, id);
+        node.set_data_field::<u16>(6, which);
+        node.get_pointer_field(0).set_text(name);
+    }
+
+    fn build(nodes: &[Node]) -> message::Builder<message::HeapAllocator> {
+        let mut message = message::Builder::new_default();
+        {
+            let Root(root) = message.init_root();
+            write_nodes(root, nodes);
+        }
+        message
+    }
+
+    fn build_request(nodes: &[Node]) -> message::Builder<message::HeapAllocator> {
+        let mut message = message::Builder::new_default();
+        {
+            let Root(root) = message.init_root();
+            let request = root.init_struct(StructSize { data: 0, pointers: 4 });
+            write_nodes(request.get_pointer_field(0), nodes);
+        }
+        message
+    }
+
+    fn write_nodes(pointer: PointerBuilder, nodes: &[Node]) {
+        let list = pointer.init_struct_list(nodes.len() as u32, StructSize { data: 5, pointers: 6 });
+        for (idx, node) in nodes.iter().enumerate() {
+            let builder = list.get_struct_element(idx as u32);
+            match *node {
+                Node::Struct(id, name, data_words, pointer_count, ref fields) => {
+                    init_node(&builder, id, name, 1);
+                    builder.set_data_field::<u16>(7, data_words);
+                    builder.set_data_field::<u16>(12, pointer_count);
+                    let has_union = fields.iter().any(|f| f.discriminant != 0xffff);
+                    builder.set_data_field::<u16>(15, if has_union { 2 } else { 0 });
+                    let field_list = builder.get_pointer_field(3).init_struct_list(
+                        fields.len() as u32, StructSize { data: 3, pointers: 4 });
+                    for (idx, f) in fields.iter().enumerate() {
+                        let fb = field_list.get_struct_element(idx as u32);
+                        fb.get_pointer_field(0).set_text(f.name);
+                        fb.set_data_field_mask::<u16>(1, f.discriminant, 0xffff);
+                        fb.set_data_field::<u32>(1, f.offset);
+                        fb.get_pointer_field(2).init_struct(StructSize { data: 3, pointers: 1 })
+                            .set_data_field::<u16>(0, f.type_which);
+                        let default = fb.get_pointer_field(3)
+                            .init_struct(StructSize { data: 2, pointers: 1 });
+                        default.set_data_field::<u16>(0, f.type_which);
+                        default.set_data_field::<u32>(1, f.default);
+                        if let Some(ordinal) = f.ordinal {
+                            fb.set_data_field::<u16>(5, 1);
+                            fb.set_data_field::<u16>(6, ordinal);
+                        }
+                    }
+                }
+                Node::Enum(id, name, ref enumerants) => {
+                    init_node(&builder, id, name, 2);
+                    let list = builder.get_pointer_field(3).init_struct_list(
+                        enumerants.len() as u32, StructSize { data: 1, pointers: 2 });
+                    for (idx, e) in enumerants.iter().enumerate() {
+                        list.get_struct_element(idx as u32).get_pointer_field(0).set_text(e);
+                    }
+                }
+                Node::Interface(id, name, ref methods) => {
+                    init_node(&builder, id, name, 3);
+                    let list = builder.get_pointer_field(3).init_struct_list(
+                        methods.len() as u32, StructSize { data: 3, pointers: 5 });
+                    for (idx, &(method_name, params, results)) in methods.iter().enumerate() {
+                        let mb = list.get_struct_element(idx as u32);
+                        mb.get_pointer_field(0).set_text(method_name);
+                        mb.set_data_field::<u64>(1, params);
+                        mb.set_data_field::<u64>(2, results);
+                    }
+                }
+            }
+        }
+    }
+
+    fn compare(old: &[Node], new: &[Node]) -> Vec<(String, IncompatibilityKind)> {
+        let old = build(old);
+        let new = build(new);
+        let old_schema = Schema::from_nodes(old.get_root_as_reader::<any_pointer::Reader>().unwrap())
+            .unwrap();
+        let new_schema = Schema::from_nodes(new.get_root_as_reader::<any_pointer::Reader>().unwrap())
+            .unwrap();
+        check(&old_schema, &new_schema).unwrap().into_iter().map(|i| (i.path, i.kind)).collect()
+    }
+
+    fn base_schema() -> Vec<Node> {
+        vec![Node::Struct(1, "foo.capnp:Foo", 2, 1,
+                          vec![field("a", UINT32, 0), field("b", UINT64, 1), field("c", TEXT, 0)]),
+             Node::Enum(2, "foo.capnp:Color", vec!["red", "green"]),
+             Node::Interface(3, "foo.capnp:Bar", vec![("baz", 10, 11)])]
+    }
+
+    #[test]
+    fn unchanged_schema() {
+        assert_eq!(compare(&base_schema(), &base_schema()), vec![]);
+    }
+
+    #[test]
+    fn compatible_changes() {
+        let new = vec![
+            Node::Struct(1, "foo.capnp:Foo", 3, 1,
+                         vec![field("alpha", UINT32, 0), field("b", UINT64, 1), field("c", DATA, 0),
+                              field("d", UINT64, 2)]),
+            Node::Enum(2, "foo.capnp:Color", vec!["red", "green", "blue"]),
+            Node::Interface(3, "foo.capnp:Bar", vec![("baz", 10, 11), ("qux", 12, 13)])];
+        assert_eq!(compare(&base_schema(), &new), vec![]);
+    }
+
+    #[test]
+    fn incompatible_changes() {
+        let mut b = field("b", UINT64, 1);
+        b.discriminant = 0;
+        let mut a = field("a", UINT32, 0);
+        a.default = 7;
+        let new = vec![
+            Node::Struct(1, "foo.capnp:Foo", 1, 1, vec![a, b]),
+            Node::Enum(2, "foo.capnp:Color", vec!["red"]),
+            Node::Interface(3, "foo.capnp:Bar", vec![("baz", 10, 12)])];
+        let result = compare(&base_schema(), &new);
+        assert_eq!(result, vec![
+            ("foo.capnp:Foo".to_string(),
+             IncompatibilityKind::StructShrunk { old_data_words: 2, new_data_words: 1,
+                                                 old_pointer_count: 1, new_pointer_count: 1 }),
+            ("foo.capnp:Foo.a".to_string(), IncompatibilityKind::DefaultValueChanged),
+            ("foo.capnp:Foo.c".to_string(), IncompatibilityKind::FieldRemoved),
+            ("foo.capnp:Color.green".to_string(), IncompatibilityKind::EnumerantRemoved),
+            ("foo.capnp:Bar.baz".to_string(), IncompatibilityKind::MethodSignatureChanged {
+                old: "@0x000000000000000a -> @0x000000000000000b".to_string(),
+                new: "@0x000000000000000a -> @0x000000000000000c".to_string() }),
+        ]);
+
+        let mut b = field("b", UINT64, 1);
+        b.discriminant = 0;
+        let mut c = field("c", TEXT, 0);
+        c.discriminant = 1;
+        let new = vec![Node::Struct(1, "foo.capnp:Foo", 2, 1,
+                                    vec![field("a", UINT64, 0), b, c])];
+        let result = compare(&base_schema(), &new);
+        assert_eq!(result, vec![
+            ("foo.capnp:Foo.a".to_string(),
+             IncompatibilityKind::FieldTypeChanged { old: "UInt32".to_string(),
+                                                     new: "UInt64".to_string() }),
+            ("foo.capnp:Foo.c".to_string(), IncompatibilityKind::UnionMembershipChanged),
+        ]);
+    }
+
+    #[test]
+    fn renamed_fields() {
+        // Swapping the names of two fields is allowed; only positions and ordinals matter.
+        let new = vec![Node::Struct(1, "foo.capnp:Foo", 2, 1,
+                                    vec![field("b", UINT32, 0), field("a", UINT64, 1),
+                                         field("c", TEXT, 0)])];
+        assert_eq!(compare(&base_schema(), &new), vec![]);
+    }
+
+    #[test]
+    fn reordered_fields() {
+        let mut old_a = field("a", UINT32, 0);
+        old_a.ordinal = Some(0);
+        let mut old_b = field("b", UINT64, 1);
+        old_b.ordinal = Some(1);
+        let mut new_a = old_a.clone();
+        new_a.ordinal = Some(1);
+        let mut new_b = old_b.clone();
+        new_b.ordinal = Some(0);
+        let old = vec![Node::Struct(1, "foo.capnp:Foo", 2, 0, vec![old_a, old_b])];
+        let new = vec![Node::Struct(1, "foo.capnp:Foo", 2, 0, vec![new_a, new_b])];
+        assert_eq!(compare(&old, &new), vec![
+            ("foo.capnp:Foo.a".to_string(),
+             IncompatibilityKind::OrdinalChanged { old: "@0".to_string(), new: "@1".to_string() }),
+            ("foo.capnp:Foo.b".to_string(),
+             IncompatibilityKind::OrdinalChanged { old: "@1".to_string(), new: "@0".to_string() }),
+        ]);
+
+        // Moving a field to another position shows up as changes to whatever is there now.
+        let new = vec![Node::Struct(1, "foo.capnp:Foo", 2, 1,
+                                    vec![field("b", UINT64, 1), field("a", UINT32, 0),
+                                         field("c", TEXT, 0)])];
+        assert_eq!(compare(&base_schema(), &new), vec![
+            ("foo.capnp:Foo.a".to_string(),
+             IncompatibilityKind::FieldTypeChanged { old: "UInt32".to_string(),
+                                                     new: "UInt64".to_string() }),
+            ("foo.capnp:Foo.b".to_string(),
+             IncompatibilityKind::FieldTypeChanged { old: "UInt64".to_string(),
+                                                     new: "UInt32".to_string() }),
+        ]);
+    }
+
+    #[test]
+    fn code_generator_request() {
+        let old = build_request(&base_schema());
+        let new = build_request(&[Node::Enum(2, "foo.capnp:Color", vec!["red"])]);
+        let old_schema = Schema::from_code_generator_request(
+            old.get_root_as_reader::<any_pointer::Reader>().unwrap()).unwrap();
+        let new_schema = Schema::from_code_generator_request(
+            new.get_root_as_reader::<any_pointer::Reader>().unwrap()).unwrap();
+        assert_eq!(old_schema.len(), 3);
+        assert_eq!(new_schema.len(), 1);
+        let result = check(&old_schema, &new_schema).unwrap();
+        assert_eq!(result, vec![Incompatibility {
+            node_id: 2,
+            path: "foo.capnp:Color.green".to_string(),
+            kind: IncompatibilityKind::EnumerantRemoved,
+        }]);
+        assert_eq!(result[0].to_string(), "foo.capnp:Color.green: enumerant removed");
+
+        // A bare List(Node) is not a CodeGeneratorRequest.
+        let nodes = build(&base_schema());
+        assert!(Schema::from_code_generator_request(
+            nodes.get_root_as_reader::<any_pointer::Reader>().unwrap()).is_err());
+    }
+}
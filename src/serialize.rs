@@ -26,7 +26,7 @@ use std::io::{Read, Write};
 
 use message;
 use util::read_exact;
-use {Error, Result, Word};
+use {Error, ErrorReason, Result, Word};
 
 use byteorder::{ByteOrder, LittleEndian};
 
@@ -56,7 +56,7 @@ pub fn read_message_from_words<'a>(slice: &'a [Word],
     let (num_words, offsets) = try!(read_segment_table(&mut bytes, options));
     let words = ::Word::bytes_to_words(bytes);
     if num_words != words.len() {
-        Err(Error::failed(
+        Err(Error::with_reason(ErrorReason::MalformedSegmentTable,
             format!("Wrong number of words. Header claimed {} words, but message has {} words",
                     num_words, words.len())))
     } else {
@@ -107,9 +107,9 @@ fn read_segment_table<R>(read: &mut R,
                                                    .wrapping_add(1) as usize;
 
     if segment_count >= 512 {
-        return Err(Error::failed(format!("Too many segments: {}", segment_count)))
+        return Err(Error::with_reason(ErrorReason::MalformedSegmentTable, format!("Too many segments: {}", segment_count)))
     } else if segment_count == 0 {
-        return Err(Error::failed(format!("Too few segments: {}", segment_count)))
+        return Err(Error::with_reason(ErrorReason::MalformedSegmentTable, format!("Too few segments: {}", segment_count)))
     }
 
     let mut segment_slices = Vec::with_capacity(segment_count);
@@ -143,7 +143,7 @@ fn read_segment_table<R>(read: &mut R,
     // traversal limit. Without this check, a malicious client could transmit a very large segment
     // size to make the receiver allocate excessive space and possibly crash.
     if total_words as u64 > options.traversal_limit_in_words  {
-        return Err(Error::failed(
+        return Err(Error::with_reason(ErrorReason::ReadLimitExceeded,
             format!("Message has {} words, which is too large. To increase the limit on the \
              receiving end, see capnp::message::ReaderOptions.", total_words)))
     }
@@ -201,6 +201,25 @@ where W: Write, A: message::Allocator {
     write_segments(write, &*segments)
 }
 
+/// Writes the segments of an already-read message to `write`, using the standard stream framing.
+///
+/// This is useful for re-encoding a message that was received in some other form, for example
+/// one that was read with `serialize_packed::read_message()`. `flush` will not be called on
+/// the writer.
+pub fn write_message_segments<W, R>(write: &mut W, segments: &R) -> ::std::io::Result<()>
+where W: Write, R: message::ReaderSegments {
+    let mut borrowed_segments: Vec<&[Word]> = Vec::new();
+    while let Some(segment) = segments.get_segment(borrowed_segments.len() as u32) {
+        borrowed_segments.push(segment);
+    }
+    if borrowed_segments.is_empty() {
+        return Err(::std::io::Error::new(::std::io::ErrorKind::InvalidInput,
+                                         "message has no segments"));
+    }
+    try!(write_segment_table(write, &borrowed_segments[..]));
+    write_segments(write, &borrowed_segments[..])
+}
+
 /// Writes a segment table to `write`.
 ///
 /// `segments` must contain at least one segment.
@@ -469,4 +488,149 @@ pub mod test {
 
         quickcheck(round_trip as fn(Vec<Vec<Word>>) -> TestResult);
     }
+
+    #[test]
+    fn validate_bounds_read_limit() {
+        let words: &[Word] = &[
+            capnp_word!(0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00), // struct, 1 pointer
+            capnp_word!(0x01, 0x00, 0x00, 0x00, 0x0d, 0x00, 0x00, 0x00), // list of one u64
+            capnp_word!(0x2a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00)];
+        let segments: &[&[Word]] = &[words];
+        let mut options = message::ReaderOptions::new();
+        options.traversal_limit_in_words(3); // root pointer, struct and list
+
+        let message = message::Reader::new(message::SegmentArray::new(segments), options);
+        message.validate().unwrap();
+        for _ in 0..message::VALIDATED_READ_FACTOR {
+            let root: ::any_pointer::Reader = message.get_root().unwrap();
+            assert_eq!(root.total_size().unwrap().word_count, 2);
+        }
+        // The budget is not unlimited: re-reading the message indefinitely still runs out.
+        let root: ::Result<::any_pointer::Reader> = message.get_root();
+        assert!(root.and_then(|root| root.total_size()).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_malformed_messages() {
+        // Far pointer into a segment that does not exist.
+        let words: &[Word] = &[capnp_word!(0x02, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00)];
+        let segments: &[&[Word]] = &[words];
+        let message = message::Reader::new(message::SegmentArray::new(segments),
+                                           message::ReaderOptions::new());
+        assert!(message.validate().is_err());
+
+        // A struct whose only pointer points back at itself.
+        let words: &[Word] = &[
+            capnp_word!(0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00),
+            capnp_word!(0xfc, 0xff, 0xff, 0xff, 0x00, 0x00, 0x01, 0x00)];
+        let segments: &[&[Word]] = &[words];
+        let message = message::Reader::new(message::SegmentArray::new(segments),
+                                           message::ReaderOptions::new());
+        assert!(message.validate().is_err());
+    }
+
+    #[test]
+    fn errors_report_location() {
+        let words: &[Word] = &[
+            capnp_word!(0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00), // struct, 2 pointers
+            capnp_word!(0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00),
+            capnp_word!(0x01, 0x00, 0x00, 0x00, 0x16, 0x00, 0x00, 0x00), // list of 2 pointers
+            capnp_word!(0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00),
+            capnp_word!(0x90, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00)]; // out of bounds
+        let segments: &[&[Word]] = &[words];
+        let message = message::Reader::new(message::SegmentArray::new(segments),
+                                           message::ReaderOptions::new());
+
+        let expected = ::ErrorLocation {
+            segment_id: 0,
+            word_offset: 4,
+            path: vec![::PathStep::Root, ::PathStep::Pointer(1), ::PathStep::ListElement(1)],
+        };
+
+        let error = message.validate().unwrap_err();
+        assert_eq!(error.location, Some(expected.clone()));
+        assert!(error.is_out_of_bounds());
+        let text = format!("{}", error);
+        assert!(text.ends_with(
+            "(segment 0, word 4; path: root \u{2192} pointer 1 \u{2192} list element 1)"),
+                "{}", text);
+
+        let root: ::any_pointer::Reader = message.get_root().unwrap();
+        let error = root.total_size().unwrap_err();
+        assert_eq!(error.location, Some(expected));
+    }
+
+    #[test]
+    fn error_reasons() {
+        use std::error::Error;
+        use ErrorReason;
+
+        // Header claims one segment of one word, but the input ends early.
+        let bytes: &[u8] = &[0, 0, 0, 0, 1, 0, 0, 0];
+        let error = read_message(&mut Cursor::new(bytes), message::ReaderOptions::new()).err().unwrap();
+        assert!(error.is_io());
+        assert!(error.source().is_some());
+
+        let mut options = message::ReaderOptions::new();
+        options.traversal_limit_in_words(0);
+        let words: &[Word] = &[capnp_word!(0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00)];
+        let segments: &[&[Word]] = &[words];
+        let message = message::Reader::new(message::SegmentArray::new(segments), options);
+        assert!(message.validate().unwrap_err().is_read_limit_exceeded());
+
+        let mut options = message::ReaderOptions::new();
+        options.nesting_limit(0);
+        let message = message::Reader::new(message::SegmentArray::new(segments), options);
+        let root: ::any_pointer::Reader = message.get_root().unwrap();
+        assert!(root.get_as::<::primitive_list::Reader<u8>>().err().unwrap()
+                .has_reason(ErrorReason::NestingLimitExceeded));
+
+        let message = message::Reader::new(message::SegmentArray::new(segments),
+                                           message::ReaderOptions::new());
+        let root: ::any_pointer::Reader = message.get_root().unwrap();
+        assert!(root.get_as::<::text::Reader>().unwrap_err()
+                .has_reason(ErrorReason::WrongPointerKind));
+    }
+
+    #[test]
+    fn check_validate_implies_total_size() {
+        fn validate_then_size(segments: Vec<Vec<Word>>) -> TestResult {
+            if segments.len() == 0 { return TestResult::discard(); }
+            let borrowed_segments: &[&[Word]] = &segments.iter()
+                                                     .map(|segment| &segment[..])
+                                                     .collect::<Vec<_>>()[..];
+            let message = message::Reader::new(message::SegmentArray::new(borrowed_segments),
+                                               message::ReaderOptions::new());
+            if message.validate().is_err() { return TestResult::discard(); }
+            let root: ::any_pointer::Reader = message.get_root().unwrap();
+            TestResult::from_bool(root.total_size().is_ok())
+        }
+
+        quickcheck(validate_then_size as fn(Vec<Vec<Word>>) -> TestResult);
+    }
+
+    #[test]
+    fn check_arbitrary_message_copy() {
+        fn copy(segments: Vec<Vec<Word>>) -> TestResult {
+            if segments.len() == 0 { return TestResult::discard(); }
+            let borrowed_segments: &[&[Word]] = &segments.iter()
+                                                     .map(|segment| &segment[..])
+                                                     .collect::<Vec<_>>()[..];
+            let message = message::Reader::new(message::SegmentArray::new(borrowed_segments),
+                                               message::ReaderOptions::new());
+            let root: ::any_pointer::Reader = match message.get_root() {
+                Ok(root) => root,
+                Err(_) => return TestResult::discard(),
+            };
+            let mut builder = message::Builder::new_default();
+            if builder.set_root(root).is_err() { return TestResult::discard(); }
+
+            // Overwriting the copy zeroes it, which must not panic either. This second copy
+            // may legitimately run out of traversal budget, so only the absence of a panic counts.
+            let _ = builder.set_root(root);
+            TestResult::passed()
+        }
+
+        quickcheck(copy as fn(Vec<Vec<Word>>) -> TestResult);
+    }
 }
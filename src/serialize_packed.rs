@@ -39,7 +39,7 @@ impl <R> PackedRead<R> where R: BufRead {
     fn get_read_buffer(&mut self) -> io::Result<(*const u8, *const u8)> {
         let buf = try!(self.inner.fill_buf());
         unsafe {
-            Ok((buf.as_ptr(), buf.get_unchecked(buf.len())))
+            Ok((buf.as_ptr(), buf.as_ptr().offset(buf.len() as isize)))
         }
     }
 }
@@ -78,7 +78,7 @@ impl <R> Read for PackedRead<R> where R: BufRead {
 
         unsafe {
             let mut out = out_buf.as_mut_ptr();
-            let out_end: *mut u8 = out_buf.get_unchecked_mut(len);
+            let out_end: *mut u8 = out_buf.as_mut_ptr().offset(len as isize);
 
             let (mut in_ptr, mut in_end) = try!(self.get_read_buffer());
             let mut buffer_begin = in_ptr;
@@ -230,8 +230,8 @@ impl <W> Write for PackedWrite<W> where W: Write {
             let mut buf_idx: usize = 0;
             let mut buf: [u8; 64] = [0; 64];
 
-            let mut in_ptr: *const u8 = in_buf.get_unchecked(0);
-            let in_end: *const u8 = in_buf.get_unchecked(in_buf.len());
+            let mut in_ptr: *const u8 = in_buf.as_ptr();
+            let in_end: *const u8 = in_buf.as_ptr().offset(in_buf.len() as isize);
 
             while in_ptr < in_end {
 
@@ -302,7 +302,7 @@ impl <W> Write for PackedWrite<W> where W: Write {
                     if ptr_sub(limit, in_word) > 255 {
                         limit = in_word.offset(255);
                     }
-                    while in_word < limit && *in_word == 0 {
+                    while in_word < limit && ptr::read_unaligned(in_word) == 0 {
                         in_word = in_word.offset(1);
                     }
 
@@ -366,6 +366,14 @@ pub fn write_message<W, A>(write: &mut W, message : &::message::Builder<A>) -> i
     serialize::write_message(&mut packed_write, message)
 }
 
+/// Writes the segments of an already-read message to `write`, using the packed encoding.
+pub fn write_message_segments<W, R>(write: &mut W, segments: &R) -> io::Result<()>
+    where W: Write, R: message::ReaderSegments
+{
+    let mut packed_write = PackedWrite { inner: write };
+    serialize::write_message_segments(&mut packed_write, segments)
+}
+
 #[cfg(test)]
 mod tests {
 
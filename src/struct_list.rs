@@ -26,7 +26,7 @@ use std::marker::PhantomData;
 use private::layout::{ListReader, ListBuilder, PointerReader, PointerBuilder, InlineComposite};
 use traits::{FromPointerReader, FromPointerBuilder,
              FromStructBuilder, FromStructReader, HasStructSize,
-             IndexMove, ListIter};
+             IndexMove, IntoInternalStructReader, ListIter};
 use Result;
 
 #[derive(Copy, Clone)]
@@ -102,15 +102,25 @@ impl <'a, T> Builder<'a, T> where T: for<'b> ::traits::OwnedStruct<'b> {
 
     pub fn len(&self) -> u32 { self.builder.len() }
 
+    /// Drops the structs from index `len` on, along with everything they point to.
+    /// Errors if `len` exceeds the length.
+    pub fn truncate(&mut self, len: u32) -> Result<()> {
+        self.builder.truncate(len)
+    }
+
+    /// Sets the number of structs to `len`; added ones have every field at its default.
+    /// Growing may copy the structs elsewhere, which leaves element builders taken before
+    /// the call pointing at zeroed memory.
+    pub fn resize(&mut self, len: u32) -> Result<()> {
+        self.builder.resize(len)
+    }
+
     pub fn as_reader(self) -> Reader<'a, T> {
         Reader {
             marker: PhantomData,
             reader: self.builder.as_reader(),
         }
     }
-
-    //        pub fn set_with_caveats(&self, index : uint, value : T) {
-    //        }
 }
 
 impl <'a, T> Builder<'a, T> where T: for<'b> ::traits::OwnedStruct<'b> {
@@ -143,6 +153,18 @@ impl <'a, T> Builder<'a, T> where T: for<'b> ::traits::OwnedStruct<'b> {
         assert!(index < self.len());
         FromStructBuilder::new(self.builder.get_struct_element(index))
     }
+
+    /// Copies `value` into the element at `index`. List elements have a fixed size, so if
+    /// `value` comes from a newer version of the schema, the fields that don't fit are
+    /// silently dropped.
+    pub fn set_with_caveats<'b>(&mut self, index: u32, value: <T as ::traits::OwnedStruct<'b>>::Reader)
+                                -> Result<()>
+        where <T as ::traits::OwnedStruct<'b>>::Reader: IntoInternalStructReader<'b>
+    {
+        assert!(index < self.len());
+        self.builder.borrow().get_struct_element(index)
+            .copy_content_from(&value.into_internal_struct_reader())
+    }
 }
 
 impl <'a, T> ::traits::SetPointerBuilder<Builder<'a, T>> for Reader<'a, T>
@@ -153,3 +175,37 @@ impl <'a, T> ::traits::SetPointerBuilder<Builder<'a, T>> for Reader<'a, T>
         pointer.set_list(&value.reader)
     }
 }
+
+#[test]
+fn set_with_caveats() {
+    let mut message = ::message::Builder::new_default();
+    {
+        // A "newer" version of the elements' struct, with an extra data word and pointer.
+        let mut st = message.init_root::<::any_pointer::Builder>().init_any_struct(2, 2);
+        st.borrow().get_data_section()[0] = 42;
+        st.borrow().get_data_section()[8] = 1;
+        st.borrow().get_pointer_field(0).set_as::<::text::Builder, _>("origin").unwrap();
+        st.borrow().get_pointer_field(1).set_as::<::text::Builder, _>("dropped").unwrap();
+    }
+
+    let mut other = ::message::Builder::new_default();
+    {
+        let src: ::any_struct::Reader = message.get_root_as_reader().unwrap();
+        let mut root = other.init_root::<::any_pointer::Builder>();
+        root.borrow().init_any_struct_list(2, 1, 1);
+        let mut list: Builder<::any_struct::Owned> = root.get_as().unwrap();
+        list.set_with_caveats(1, src).unwrap();
+        list.set_with_caveats(1, src).unwrap();
+
+        let list = list.as_reader();
+        assert_eq!(list.get(0).get_data_section(), &[0; 8]);
+        assert_eq!(list.get(1).get_data_section(), &[42, 0, 0, 0, 0, 0, 0, 0]);
+        let name: ::text::Reader = list.get(1).get_pointer_field(0).get_as().unwrap();
+        assert_eq!(name, "origin");
+    }
+
+    // The elements kept their size and only the first text was copied: the list tag,
+    // two 2-word elements and one word of text.
+    let size = other.get_root_as_reader::<::any_pointer::Reader>().unwrap().total_size().unwrap();
+    assert_eq!(size.word_count, 1 + 2 * 2 + 1);
+}
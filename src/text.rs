@@ -21,9 +21,10 @@
 
 //! UTF-8 encoded text.
 
-use std::{convert, str, ops, ptr};
+use std::{convert, fmt, str, ops, ptr};
 
-use {Error, Result};
+use private::layout::{GrowableBlob, PointerBuilder};
+use {Error, ErrorReason, Result};
 
 #[derive(Copy, Clone)]
 pub struct Owned(());
@@ -38,8 +39,8 @@ pub type Reader<'a> = &'a str;
 pub fn new_reader<'a>(v : &'a [u8]) -> Result<Reader<'a>> {
     match str::from_utf8(v) {
         Ok(v) => Ok(v),
-        Err(e) => Err(Error::failed(
-            format!("Text contains non-utf8 data: {:?}", e))),
+        Err(e) => Err(Error::with_reason(ErrorReason::BadUtf8,
+            format!("Text contains non-utf8 data: {:?}", e)).with_source(e)),
     }
 }
 
@@ -58,8 +59,8 @@ impl <'a> Builder <'a> {
     pub fn new<'b>(bytes: &'b mut [u8], pos: u32) -> Result<Builder<'b>> {
         if pos != 0 {
             match str::from_utf8(bytes) {
-                Err(e) => return Err(Error::failed(
-                    format!("Text contains non-utf8 data: {:?}", e))),
+                Err(e) => return Err(Error::with_reason(ErrorReason::BadUtf8,
+                    format!("Text contains non-utf8 data: {:?}", e)).with_source(e)),
                 _ => {}
             }
         }
@@ -112,6 +113,85 @@ impl <'a> ::traits::FromPointerBuilder<'a> for Builder<'a> {
     }
 }
 
+/// A text field that grows as text is appended to it, for filling in a field whose final
+/// length is not known up front. See `data::GrowableBuilder`, which grows the same way. The
+/// field is trimmed to its contents by `into_builder()`, or when the builder is dropped.
+pub struct GrowableBuilder<'a> {
+    blob: GrowableBlob<'a>,
+}
+
+impl <'a> GrowableBuilder<'a> {
+    pub fn len(&self) -> u32 { self.blob.len() as u32 }
+
+    pub fn is_empty(&self) -> bool { self.blob.is_empty() }
+
+    pub fn push(&mut self, c: char) -> Result<()> {
+        let mut buf = [0; 4];
+        self.push_str(c.encode_utf8(&mut buf))
+    }
+
+    pub fn push_str(&mut self, string: &str) -> Result<()> {
+        self.blob.extend_from_slice(string.as_bytes())
+    }
+
+    /// Shortens the text to `len` bytes. Panics if `len` is greater than the current length or
+    /// does not lie on a char boundary.
+    pub fn truncate(&mut self, len: u32) {
+        assert!(self.is_char_boundary(len as usize), "text::GrowableBuilder::truncate() on a non-char boundary");
+        self.blob.truncate(len as usize);
+    }
+
+    pub fn clear(&mut self) {
+        self.blob.truncate(0);
+    }
+
+    pub fn into_builder(mut self) -> Result<Builder<'a>> {
+        let bytes = try!(self.blob.finish());
+        let len = bytes.len() as u32;
+        Builder::new(bytes, len)
+    }
+}
+
+impl <'a> Drop for GrowableBuilder<'a> {
+    fn drop(&mut self) {
+        // This only fails if the field was replaced behind our back, leaving nothing to trim.
+        let _ = self.blob.finish();
+    }
+}
+
+impl <'a> fmt::Write for GrowableBuilder<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s).map_err(|_| fmt::Error)
+    }
+}
+
+impl <'a> ops::Deref for GrowableBuilder<'a> {
+    type Target = str;
+    fn deref(&self) -> &str {
+        str::from_utf8(self.blob.as_slice())
+            .expect("text::GrowableBuilder contents are checked for utf8-validity upon construction")
+    }
+}
+
+impl <'a> convert::AsRef<str> for GrowableBuilder<'a> {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl <'a> ::traits::FromPointerBuilder<'a> for GrowableBuilder<'a> {
+    /// Initializes an empty text field with room for `size` bytes.
+    fn init_pointer(builder: PointerBuilder<'a>, size: u32) -> GrowableBuilder<'a> {
+        builder.init_text(size);
+        let blob = GrowableBlob::new(builder, 0, 1).expect("failed to take over new text");
+        GrowableBuilder { blob: blob }
+    }
+    fn get_from_pointer(builder: PointerBuilder<'a>) -> Result<GrowableBuilder<'a>> {
+        let len = try!(builder.get_text(::std::ptr::null(), 0)).len() as u32;
+        Ok(GrowableBuilder { blob: try!(GrowableBlob::new(builder, len, 1)) })
+    }
+}
+
 impl <'a> ::traits::SetPointerBuilder<Builder<'a>> for Reader<'a> {
     fn set_pointer_builder<'b>(pointer: ::private::layout::PointerBuilder<'b>, value: Reader<'a>)
                                -> Result<()>
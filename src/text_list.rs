@@ -81,6 +81,18 @@ impl <'a> Builder<'a> {
 
     pub fn len(&self) -> u32 { self.builder.len() }
 
+    /// Drops the texts from index `len` on, zeroing their bytes.
+    /// Errors if `len` exceeds the length.
+    pub fn truncate(&mut self, len: u32) -> Result<()> {
+        self.builder.truncate(len)
+    }
+
+    /// Sets the number of texts to `len`; added ones are null. Growing may relocate the list,
+    /// so fetch fresh text builders afterwards.
+    pub fn resize(&mut self, len: u32) -> Result<()> {
+        self.builder.resize(len)
+    }
+
     pub fn set(&mut self, index: u32, value: ::text::Reader) {
         assert!(index < self.len());
         self.builder.borrow().get_pointer_element(index).set_text(value);
@@ -96,6 +108,12 @@ impl <'a> Builder<'a> {
 }
 
 
+impl <'a, 'b> ::traits::SetListElement<::text::Reader<'b>> for Builder<'a> {
+    fn set_element(&mut self, index: u32, value: ::text::Reader<'b>) {
+        self.set(index, value)
+    }
+}
+
 impl <'a> FromPointerBuilder<'a> for Builder<'a> {
     fn init_pointer(builder: PointerBuilder<'a>, size: u32) -> Builder<'a> {
         Builder {
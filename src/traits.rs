@@ -28,6 +28,12 @@ pub trait FromStructReader<'a> {
     fn new(reader : StructReader<'a>) -> Self;
 }
 
+/// Gives access to the untyped reader underlying a struct reader. Struct readers implement it so
+/// that code such as `struct_list::Builder::set_with_caveats()` can copy straight from them.
+pub trait IntoInternalStructReader<'a> {
+    fn into_internal_struct_reader(self) -> StructReader<'a>;
+}
+
 pub trait HasStructSize {
     fn struct_size() -> StructSize;
 }
@@ -82,6 +88,12 @@ pub trait SetPointerBuilder<To> {
     fn set_pointer_builder<'a>(PointerBuilder<'a>, Self) -> Result<()>;
 }
 
+/// A list builder whose elements can be set from values of type `V`.
+/// See `any_pointer::Builder::init_from_iter()`.
+pub trait SetListElement<V> {
+    fn set_element(&mut self, index: u32, value: V);
+}
+
 pub trait Imbue<'a> {
     fn imbue(&mut self, &'a CapTable);
 }